@@ -1,47 +1,499 @@
-use anchor_lang::prelude::*;
 use crate::constants::*;
-use crate::utils::*;
+use crate::Error;
+use anchor_lang::prelude::*;
+use fixed_point::U256;
+
+mod fixed_point {
+    #![allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+// All curves are priced in lamports per whole token. Linear and quadratic coefficients are
+// fixed-point (scaled by CURVE_COEFF_SCALE), the exponential growth rate is scaled by WAD, so
+// every curve is evaluated in integers over the atomic supply.
+pub trait BondingCurve {
+    // checks the curve parameters against the program bounds
+    fn validate(&self) -> Result<()>;
+    // spot price at atomic `supply`, in lamports per whole token (rounded down)
+    fn price(&self, supply: u64) -> Result<U256>;
+    // area under the price curve over atomic supply [0, supply] in lamports, as a numerator
+    // over `integral_denominator`. Must never decrease as supply grows.
+    fn integral(&self, supply: u64) -> Result<U256>;
+    fn integral_denominator(&self) -> U256;
+    // largest supply in [lower, u64::MAX] whose integral does not exceed `target`. `lower` must
+    // itself satisfy that bound. Binary searches the integral by default; a supply the curve
+    // can't evaluate counts as over the target.
+    fn supply_at_integral(&self, target: U256, lower: u64) -> Result<u64> {
+        let (mut lo, mut hi) = (lower, u64::MAX);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2 + 1;
+            match self.integral(mid) {
+                Ok(area) if area <= target => lo = mid,
+                _ => hi = mid - 1,
+            }
+        }
+        Ok(lo)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveKind {
+    Linear(LinearCurve),
+    Quadratic(QuadraticCurve),
+    ConstantProduct(ConstantProductCurve),
+    Exponential(ExponentialCurve),
+}
+
+impl CurveKind {
+    pub fn curve(&self) -> &dyn BondingCurve {
+        match self {
+            CurveKind::Linear(curve) => curve,
+            CurveKind::Quadratic(curve) => curve,
+            CurveKind::ConstantProduct(curve) => curve,
+            CurveKind::Exponential(curve) => curve,
+        }
+    }
+}
+
+impl Default for CurveKind {
+    fn default() -> Self {
+        CurveKind::Quadratic(QuadraticCurve {
+            a: 100_000_000, // 1e-4 - Impact: High - Dominates at large supply values, causing exponential increase
+            b: 100_000_000_000, // 1e-1 - Impact: Moderate - Influences both initial and ongoing price increases
+            c: MIN_TOK_PRICE * CURVE_COEFF_SCALE as u64, // Impact: Low - Sets the minimum price and initial price floor
+        })
+    }
+}
+
+fn check_start_price(curve: &dyn BondingCurve) -> Result<()> {
+    let start_price = curve.price(0)?;
+    require!(
+        start_price >= U256::from(MIN_TOK_PRICE) && start_price <= U256::from(MAX_START_PRICE),
+        Error::InvalidCurveParameters
+    );
+    Ok(())
+}
 
-// Quadratic bonding curve constants
-const A: f64 = 1e-13; // Impact: High - Dominates at large supply values, causing exponential increase
-const B: f64 = 1e-10; // Impact: Moderate - Influences both initial and ongoing price increases
-const C: f64 = MIN_TOK_PRICE; // Impact: Low - Sets the minimum price and initial price floor
+// price(n) = slope * n + base_price
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearCurve {
+    pub slope: u64,
+    pub base_price: u64,
+}
+
+impl BondingCurve for LinearCurve {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.slope > 0 && self.slope <= MAX_LINEAR_SLOPE,
+            Error::InvalidCurveParameters
+        );
+        check_start_price(self)
+    }
+    fn price(&self, supply: u64) -> Result<U256> {
+        let s = U256::from(supply);
+        let t = U256::from(TOKEN_SCALE);
+        Ok(
+            (U256::from(self.slope) * s + U256::from(self.base_price) * t)
+                / (t * U256::from(CURVE_COEFF_SCALE)),
+        )
+    }
+    fn integral(&self, supply: u64) -> Result<U256> {
+        let s = U256::from(supply);
+        let t = U256::from(TOKEN_SCALE);
+        Ok(U256::from(self.slope) * s * s + U256::from(2) * U256::from(self.base_price) * t * s)
+    }
+    fn integral_denominator(&self) -> U256 {
+        let t = U256::from(TOKEN_SCALE);
+        U256::from(2) * t * t * U256::from(CURVE_COEFF_SCALE)
+    }
+}
+
+// price(n) = a * n^2 + b * n + c
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuadraticCurve {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl BondingCurve for QuadraticCurve {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.a > 0 && self.a <= MAX_QUADRATIC_A && self.b <= MAX_LINEAR_SLOPE,
+            Error::InvalidCurveParameters
+        );
+        check_start_price(self)
+    }
+    fn price(&self, supply: u64) -> Result<U256> {
+        let s = U256::from(supply);
+        let t = U256::from(TOKEN_SCALE);
+        Ok(
+            (U256::from(self.a) * s * s + U256::from(self.b) * t * s + U256::from(self.c) * t * t)
+                / (t * t * U256::from(CURVE_COEFF_SCALE)),
+        )
+    }
+    fn integral(&self, supply: u64) -> Result<U256> {
+        let s = U256::from(supply);
+        let t = U256::from(TOKEN_SCALE);
+        Ok(U256::from(2) * U256::from(self.a) * s * s * s
+            + U256::from(3) * U256::from(self.b) * t * s * s
+            + U256::from(6) * U256::from(self.c) * t * t * s)
+    }
+    fn integral_denominator(&self) -> U256 {
+        let t = U256::from(TOKEN_SCALE);
+        U256::from(6) * t * t * t * U256::from(CURVE_COEFF_SCALE)
+    }
+}
 
-fn price_function(n: f64) -> f64 {
-    A * n.powf(2.0) + B * n + C
+// pump.fun style: the curve holds virtual reserves with virtual_sol * virtual_token = k,
+// selling tokens out of the virtual token reserve
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstantProductCurve {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
 }
-fn integral_function(n: f64) -> f64 {
-    (A / 3.0) * n.powf(3.0) + (B / 2.0) * n.powf(2.0) + C * n
+
+impl ConstantProductCurve {
+    fn token_reserves(&self, supply: u64) -> Result<U256> {
+        require!(
+            supply < self.virtual_token_reserves,
+            Error::CurveSupplyExhausted
+        );
+        Ok(U256::from(self.virtual_token_reserves - supply))
+    }
+    fn k(&self) -> U256 {
+        U256::from(self.virtual_sol_reserves) * U256::from(self.virtual_token_reserves)
+    }
 }
 
-pub fn calculate_price(current_supply: u64, amount: u64, is_selling: bool) -> (u64, u64) {
+impl BondingCurve for ConstantProductCurve {
+    fn validate(&self) -> Result<()> {
+        require!(
+            (MIN_VIRTUAL_SOL_RESERVES..=MAX_VIRTUAL_SOL_RESERVES)
+                .contains(&self.virtual_sol_reserves)
+                && (MIN_VIRTUAL_TOKEN_RESERVES..=MAX_VIRTUAL_TOKEN_RESERVES)
+                    .contains(&self.virtual_token_reserves),
+            Error::InvalidCurveParameters
+        );
+        check_start_price(self)
+    }
+    fn price(&self, supply: u64) -> Result<U256> {
+        let token_reserves = self.token_reserves(supply)?;
+        Ok(self.k() * U256::from(TOKEN_SCALE) / (token_reserves * token_reserves))
+    }
+    fn integral(&self, supply: u64) -> Result<U256> {
+        // SOL reserves above the virtual starting reserves
+        let t = U256::from(TOKEN_SCALE);
+        let token_reserves = self.token_reserves(supply)?;
+        Ok(self.k() * t / token_reserves - U256::from(self.virtual_sol_reserves) * t)
+    }
+    fn integral_denominator(&self) -> U256 {
+        U256::from(TOKEN_SCALE)
+    }
+    fn supply_at_integral(&self, target: U256, _lower: u64) -> Result<u64> {
+        // floor(k * T / r) - Vs * T <= target holds for every token reserve r above
+        // k * T / (target + Vs * T + 1), so solve for the smallest such reserve directly
+        let t = U256::from(TOKEN_SCALE);
+        let bound = target + U256::from(self.virtual_sol_reserves) * t + U256::from(1);
+        let min_token_reserves = self.k() * t / bound + U256::from(1);
+        let min_token_reserves =
+            u64::try_from(min_token_reserves).map_err(|_| error!(Error::MathOverflow))?;
+        self.virtual_token_reserves
+            .checked_sub(min_token_reserves)
+            .ok_or(error!(Error::MathOverflow))
+    }
+}
+
+// price(n) = base_price * e^(growth_rate * n)
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExponentialCurve {
+    pub base_price: u64,
+    pub growth_rate: u64,
+}
+
+impl ExponentialCurve {
+    // e^(growth_rate * n), scaled by WAD
+    fn growth(&self, supply: u64) -> Result<U256> {
+        let exponent = U256::from(self.growth_rate) * U256::from(supply) / U256::from(TOKEN_SCALE);
+        exp_wad(exponent)
+    }
+}
+
+impl BondingCurve for ExponentialCurve {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.growth_rate > 0 && self.growth_rate <= MAX_EXP_GROWTH_RATE,
+            Error::InvalidCurveParameters
+        );
+        check_start_price(self)
+    }
+    fn price(&self, supply: u64) -> Result<U256> {
+        Ok(U256::from(self.base_price) * self.growth(supply)? / U256::from(WAD))
+    }
+    fn integral(&self, supply: u64) -> Result<U256> {
+        // base_price / growth_rate * (e^(growth_rate * n) - 1)
+        Ok(U256::from(self.base_price) * (self.growth(supply)? - U256::from(WAD)))
+    }
+    fn integral_denominator(&self) -> U256 {
+        U256::from(self.growth_rate)
+    }
+}
+
+// e^x for a WAD scaled x in [0, MAX_EXP_INPUT]. The input is halved EXP_SQUARINGS times so the
+// Taylor series converges quickly, then squared back up. Every step rounds down, which keeps the
+// result non-decreasing in x.
+fn exp_wad(x: U256) -> Result<U256> {
+    require!(x <= U256::from(MAX_EXP_INPUT), Error::MathOverflow);
+    let wad = U256::from(WAD);
+    let y = x >> EXP_SQUARINGS;
+    let mut result = wad;
+    let mut term = wad;
+    let mut i = 1u64;
+    while !term.is_zero() {
+        term = term * y / (wad * U256::from(i));
+        result += term;
+        i += 1;
+    }
+    for _ in 0..EXP_SQUARINGS {
+        result = result * result / wad;
+    }
+    Ok(result)
+}
+
+fn to_lamports(value: U256) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(Error::MathOverflow))
+}
+
+pub fn calculate_spot_price(curve_kind: &CurveKind, supply: u64) -> Result<u64> {
+    to_lamports(curve_kind.curve().price(supply)?)
+}
+
+pub fn calculate_price(
+    curve_kind: &CurveKind,
+    current_supply: u64,
+    amount: u64,
+    is_selling: bool,
+) -> Result<(u64, u64)> {
     msg!("========Calculating price========");
-    // Convert atomic units to whole units
-    let current_supply_units = to_whole_units(current_supply);
-    let amount_units = to_whole_units(amount);
-    let new_supply_units = if is_selling {
-        current_supply_units - amount_units
+    let curve = curve_kind.curve();
+    let new_supply = if is_selling {
+        current_supply.checked_sub(amount)
     } else {
-        current_supply_units + amount_units
-    };
+        current_supply.checked_add(amount)
+    }
+    .ok_or(Error::MathOverflow)?;
 
-    let total_cost_f64 = if is_selling {
-        integral_function(current_supply_units) - integral_function(new_supply_units)
+    let (lower_supply, upper_supply) = if is_selling {
+        (new_supply, current_supply)
     } else {
-        integral_function(new_supply_units) - integral_function(current_supply_units)
+        (current_supply, new_supply)
     };
+    let area = curve
+        .integral(upper_supply)?
+        .checked_sub(curve.integral(lower_supply)?)
+        .ok_or(Error::MathOverflow)?;
+    let denominator = curve.integral_denominator();
 
-    let total_cost_lamports = to_atomic_units(total_cost_f64);
+    // Round up what the buyer pays and down what the seller receives so the pool never loses
+    // lamports to rounding
+    let total_cost = if is_selling {
+        area / denominator
+    } else {
+        (area + denominator - 1) / denominator
+    };
+    let total_cost_lamports = to_lamports(total_cost)?;
 
-    // Calculate price per whole unit correctly
-    let price_per_unit_f64 = price_function(new_supply_units);
-    let price_per_unit = to_atomic_units(price_per_unit_f64);
+    let price_per_unit = to_lamports(curve.price(new_supply)?)?;
 
-    msg!("Old supply (units): {}", current_supply_units);
-    msg!("Amount (units): {}", amount_units);
-    msg!("New supply (units): {}", new_supply_units);
+    msg!("Old supply: {}", current_supply);
+    msg!("Amount: {}", amount);
+    msg!("New supply: {}", new_supply);
     msg!("Total cost: {}", total_cost_lamports);
     msg!("Price per whole unit: {}", price_per_unit);
 
-    (total_cost_lamports, price_per_unit)
+    Ok((total_cost_lamports, price_per_unit))
+}
+
+// number of atomic tokens `sol_in` lamports buy at `current_supply`. Buys round their cost up, so
+// the cost of the returned amount never exceeds `sol_in`.
+pub fn calculate_buy_amount(
+    curve_kind: &CurveKind,
+    current_supply: u64,
+    sol_in: u64,
+) -> Result<u64> {
+    let curve = curve_kind.curve();
+    let target =
+        curve.integral(current_supply)? + U256::from(sol_in) * curve.integral_denominator();
+    let new_supply = curve.supply_at_integral(target, current_supply)?;
+    let amount = new_supply
+        .checked_sub(current_supply)
+        .ok_or(Error::MathOverflow)?;
+
+    msg!("SOL in: {}", sol_in);
+    msg!("Tokens out: {}", amount);
+
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // 10M whole tokens already costs ~33M SOL on the default curve
+    const MAX_SUPPLY: u64 = 10_000_000 * TOKEN_SCALE;
+
+    fn curves() -> Vec<CurveKind> {
+        vec![
+            CurveKind::Linear(LinearCurve {
+                slope: 1_000_000_000,
+                base_price: MIN_TOK_PRICE * CURVE_COEFF_SCALE as u64,
+            }),
+            CurveKind::default(),
+            CurveKind::ConstantProduct(ConstantProductCurve {
+                virtual_sol_reserves: 30 * 1_000_000_000,
+                virtual_token_reserves: 1_073_000_000 * TOKEN_SCALE,
+            }),
+            CurveKind::Exponential(ExponentialCurve {
+                base_price: 10,
+                growth_rate: 10_000_000_000_000, // 1e-5 per whole token
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn buy_then_sell_never_returns_more_than_paid(
+            curve_index in 0..4usize,
+            supply in 0..MAX_SUPPLY,
+            amount in 1..MAX_SUPPLY,
+        ) {
+            let curve = curves()[curve_index];
+            if let Ok((paid, _)) = calculate_price(&curve, supply, amount, false) {
+                let (received, _) = calculate_price(&curve, supply + amount, amount, true).unwrap();
+                prop_assert!(received <= paid);
+            }
+        }
+
+        #[test]
+        fn split_buys_then_full_sell_never_returns_more_than_paid(
+            curve_index in 0..4usize,
+            supply in 0..MAX_SUPPLY,
+            amounts in prop::collection::vec(1..MAX_SUPPLY / 16, 1..16),
+        ) {
+            let curve = curves()[curve_index];
+            let mut current_supply = supply;
+            let mut paid = 0u128;
+            for amount in &amounts {
+                match calculate_price(&curve, current_supply, *amount, false) {
+                    Ok((cost, _)) => {
+                        paid += u128::from(cost);
+                        current_supply += amount;
+                    }
+                    Err(_) => break,
+                }
+            }
+            let bought = current_supply - supply;
+            // a sell too large to pay out in a single u64 fails instead
+            if let Ok((received, _)) = calculate_price(&curve, current_supply, bought, true) {
+                prop_assert!(u128::from(received) <= paid);
+            }
+        }
+
+        #[test]
+        fn buy_amount_is_the_most_sol_in_can_afford(
+            curve_index in 0..4usize,
+            supply in 0..MAX_SUPPLY,
+            sol_in in 0..1_000_000 * 1_000_000_000u64,
+        ) {
+            let curve = curves()[curve_index];
+            if let Ok(amount) = calculate_buy_amount(&curve, supply, sol_in) {
+                // the spot price past the purchase may still overflow a u64
+                if let Ok((cost, _)) = calculate_price(&curve, supply, amount, false) {
+                    prop_assert!(cost <= sol_in);
+                }
+                if let Ok((cost, _)) = calculate_price(&curve, supply, amount + 1, false) {
+                    prop_assert!(cost > sol_in);
+                }
+            }
+        }
+
+        #[test]
+        fn price_never_decreases_with_supply(
+            curve_index in 0..4usize,
+            supply in 0..MAX_SUPPLY,
+            amount in 1..MAX_SUPPLY,
+        ) {
+            let curve = curves()[curve_index];
+            if let Ok((_, price_after)) = calculate_price(&curve, supply, amount, false) {
+                let (_, price_before) = calculate_price(&curve, 0, supply, false).unwrap();
+                prop_assert!(price_after >= price_before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_curves_are_valid() {
+        for curve in curves() {
+            curve.curve().validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_parameters() {
+        let zero_start = CurveKind::Linear(LinearCurve {
+            slope: 1,
+            base_price: 0,
+        });
+        let no_growth = CurveKind::Exponential(ExponentialCurve {
+            base_price: 10,
+            growth_rate: 0,
+        });
+        let tiny_reserves = CurveKind::ConstantProduct(ConstantProductCurve {
+            virtual_sol_reserves: 1,
+            virtual_token_reserves: 1,
+        });
+        for curve in [zero_start, no_growth, tiny_reserves] {
+            assert!(curve.curve().validate().is_err());
+        }
+    }
+
+    #[test]
+    fn matches_the_reference_curve() {
+        // 30k whole tokens: (1e-4 / 3) * 30000^3 + (1e-1 / 2) * 30000^2 + 30000 lamports
+        let (cost, price) =
+            calculate_price(&CurveKind::default(), 0, 30_000 * TOKEN_SCALE, false).unwrap();
+        assert_eq!(cost, 945_030_000);
+        // 1e-4 * 30000^2 + 1e-1 * 30000 + 1 lamports
+        assert_eq!(price, 93_001);
+    }
+
+    #[test]
+    fn exp_wad_matches_e() {
+        // e = 2.718281828459045235...
+        let e = exp_wad(U256::from(WAD)).unwrap();
+        assert!(e.as_u128().abs_diff(2_718_281_828_459_045_235) < 1_000);
+    }
+
+    #[test]
+    fn cannot_sell_more_than_supply() {
+        assert!(calculate_price(&CurveKind::default(), 10, 11, true).is_err());
+    }
+
+    #[test]
+    fn buy_amount_inverts_the_reference_curve() {
+        let amount = calculate_buy_amount(&CurveKind::default(), 0, 945_030_000).unwrap();
+        assert_eq!(amount, 30_000 * TOKEN_SCALE);
+    }
+
+    #[test]
+    fn cost_overflow_is_an_error() {
+        for curve in &curves()[1..] {
+            assert!(calculate_price(curve, 0, u64::MAX, false).is_err());
+        }
+    }
 }
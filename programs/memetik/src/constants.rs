@@ -1,11 +1,49 @@
+use crate::LpPolicy;
 use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
 
 pub const DEFAULT_TOKEN_DECIMALS: u8 = 9;
-pub const TOKEN_SCALE: f64 = 1_000_000_000.0; // 10^9, precomputed for TOKEN_DECIMALS = 9
+pub const MAX_TICKER_LEN: usize = 10; // metaplex caps the token symbol at 10 bytes
+pub const TOKEN_SCALE: u64 = 1_000_000_000; // 10^9, precomputed for TOKEN_DECIMALS = 9
 pub const SECONDS_IN_A_HOUR: i64 = 60 * 60;
 pub const SECONDS_IN_A_DAY: i64 = SECONDS_IN_A_HOUR * 24;
 
 pub const REQUIRED_ESCROW_AMOUNT: u64 = 1 * LAMPORTS_PER_SOL; // in lamports
-pub const MIN_TOK_PRICE: f64 = 1.0 / TOKEN_SCALE; // 100 lamports in atomic units
+pub const MIN_TOK_PRICE: u64 = 1; // in lamports per whole token
+pub const CURVE_COEFF_SCALE: u128 = 1_000_000_000_000; // fixed-point scale of the bonding curve coefficients
+pub const WAD: u128 = 1_000_000_000_000_000_000; // fixed-point scale of the exponential curve
 pub const DAYS_TO_MATURITY: i64 = 2; // number of days the pool has to reach milestone
+#[cfg(not(feature = "test-maturity"))]
+pub const MIN_MATURITY_DURATION: i64 = SECONDS_IN_A_HOUR; // shortest maturity window the config allows
+#[cfg(feature = "test-maturity")]
+pub const MIN_MATURITY_DURATION: i64 = 1; // seconds long windows, for the test suite only
+pub const MAX_MATURITY_DURATION: i64 = 30 * SECONDS_IN_A_DAY; // longest maturity window the config allows
+#[cfg(not(feature = "test-maturity"))]
+pub const GRADUATION_GRACE_PERIOD: i64 = SECONDS_IN_A_DAY; // a matured pool still on the curve this long past its maturity time can fail
+#[cfg(feature = "test-maturity")]
+pub const GRADUATION_GRACE_PERIOD: i64 = 30;
+pub const MIN_MATURITY_BALANCE: u64 = LAMPORTS_PER_SOL / 2; // graduation spends ~0.2 SOL on cp-swap rent and fees
 pub const REQUIRED_POOL_BALANCE_TO_MATURE: u64 = 1 * LAMPORTS_PER_SOL; // amount required for a pool to mature (convert to AMM) - effectively the market
+pub const PROTOCOL_FEE_BPS: u16 = 50; // 0.5% of every trade to the protocol treasury
+pub const CREATOR_FEE_BPS: u16 = 50; // 0.5% of every trade to the pool creator
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_TOTAL_FEE_BPS: u64 = 1_000; // protocol and creator fees combined can't pass 10%
+pub const MAX_TRANSFER_FEE_BPS: u16 = 1_000; // Token-2022 launches can't tax transfers past 10%
+pub const FORFEIT_FAILED_ESCROW: bool = false; // failed pools refund the creator's escrow by default
+pub const DEFAULT_TOTAL_SUPPLY: u64 = 50_000 * TOKEN_SCALE; // 50k whole tokens
+pub const DEFAULT_CURVE_SUPPLY: u64 = 40_000 * TOKEN_SCALE; // ~2.2 SOL on the default curve, the other 20% seeds the AMM
+pub const MIN_LP_RESERVE_BPS: u64 = 1_000; // the curve can sell at most 90% of the total supply
+pub const UNIQUE_TICKERS: bool = false; // any number of pools can share a ticker by default
+pub const DEFAULT_LP_POLICY: LpPolicy = LpPolicy::Burn; // graduated liquidity can never be pulled by default
+pub const MAX_LP_VESTING: i64 = 4 * 365 * SECONDS_IN_A_DAY; // longest cliff plus vesting an LP policy can set
+
+// Bonding curve parameter bounds
+pub const MAX_START_PRICE: u64 = 1_000_000; // in lamports per whole token
+pub const MAX_LINEAR_SLOPE: u64 = 1_000 * CURVE_COEFF_SCALE as u64; // lamports per whole token, per whole token
+pub const MAX_QUADRATIC_A: u64 = CURVE_COEFF_SCALE as u64; // lamports per whole token, per whole token squared
+pub const MIN_VIRTUAL_SOL_RESERVES: u64 = LAMPORTS_PER_SOL;
+pub const MAX_VIRTUAL_SOL_RESERVES: u64 = 1_000 * LAMPORTS_PER_SOL;
+pub const MIN_VIRTUAL_TOKEN_RESERVES: u64 = 1_000_000 * TOKEN_SCALE;
+pub const MAX_VIRTUAL_TOKEN_RESERVES: u64 = 10_000_000_000 * TOKEN_SCALE;
+pub const MAX_EXP_GROWTH_RATE: u64 = (WAD / 1_000) as u64; // 0.1% per whole token
+pub const MAX_EXP_INPUT: u128 = 64 * WAD;
+pub const EXP_SQUARINGS: usize = 6; // 2^6 = 64, keeps the exp Taylor series input <= 1
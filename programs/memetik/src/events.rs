@@ -0,0 +1,90 @@
+use crate::bonding_curve::CurveKind;
+use crate::LpPolicy;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub ticker: String,
+    pub curve: CurveKind,
+    pub tok_price: u64,
+    pub escrow_amount: u64,
+    pub maturity_time: i64,
+    pub maturity_balance: u64,
+    pub total_supply: u64,
+    pub curve_supply: u64,
+    pub trading_start_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Trade {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub trader: Pubkey,
+    pub side: TradeSide,
+    pub token_amount: u64,
+    pub sol_amount: u64, // lamports moved along the curve, before fees
+    pub protocol_fee: u64,
+    pub creator_fee: u64,
+    pub tok_price: u64, // price per whole token after the trade
+    pub supply: u64,    // mint supply after the trade
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolMatured {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub pool_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolGraduated {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub amm_pool: Pubkey,
+    pub token_amount: u64,
+    pub wsol_amount: u64,
+    pub lp_amount: u64,
+    pub lp_policy: LpPolicy,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpTokensClaimed {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub lp_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub escrow_refunded: u64,
+    pub escrow_forfeited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RefundClaimed {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub holder: Pubkey,
+    pub token_amount: u64,
+    pub sol_amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,120 @@
+use crate::Error;
+use anchor_lang::prelude::*;
+
+// Anti-sniper settings a creator picks at launch. Every limit is off at zero, so the default
+// pool trades from the moment it is created with no caps.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct LaunchSettings {
+    pub trading_start_time: i64, // unix seconds buys open at, zero or past times open at launch
+    pub max_wallet_tokens: u64,  // tokens a wallet can buy while the wallet cap holds
+    pub wallet_cap_duration: i64, // seconds after trading starts the wallet cap holds for
+    pub wallet_cap_supply: u64,  // tokens the curve sells before the wallet cap lifts
+    pub max_buy_tokens: u64,     // per-transaction buy cap when trading starts
+    pub max_buy_growth: u64,     // tokens per second the per-transaction cap rises by
+}
+
+impl LaunchSettings {
+    // pins the trading start to the launch and checks it opens before the pool expires
+    pub fn resolve(mut self, now: i64, maturity_time: i64) -> Result<Self> {
+        self.trading_start_time = self.trading_start_time.max(now);
+        require!(
+            self.trading_start_time < maturity_time && self.wallet_cap_duration >= 0,
+            Error::InvalidLaunchSettings
+        );
+        Ok(self)
+    }
+
+    // the wallet cap holds until both its time and supply windows are over
+    pub fn wallet_cap_active(&self, now: i64, supply: u64) -> bool {
+        let cap_end_time = self
+            .trading_start_time
+            .saturating_add(self.wallet_cap_duration);
+        self.max_wallet_tokens > 0 && (now < cap_end_time || supply < self.wallet_cap_supply)
+    }
+
+    pub fn max_buy_at(&self, now: i64) -> u64 {
+        if self.max_buy_tokens == 0 {
+            return u64::MAX;
+        }
+        let elapsed = now.saturating_sub(self.trading_start_time).max(0) as u64;
+        self.max_buy_tokens
+            .saturating_add(self.max_buy_growth.saturating_mul(elapsed))
+    }
+
+    // `bought` is what the wallet has bought from the pool before this trade
+    pub fn check_buy(&self, now: i64, supply: u64, bought: u64, amount: u64) -> Result<()> {
+        require!(now >= self.trading_start_time, Error::TradingNotStarted);
+        require!(amount <= self.max_buy_at(now), Error::BuyCapExceeded);
+        if self.wallet_cap_active(now, supply) {
+            require!(
+                bought.saturating_add(amount) <= self.max_wallet_tokens,
+                Error::WalletCapExceeded
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 1_000;
+
+    fn settings() -> LaunchSettings {
+        LaunchSettings {
+            trading_start_time: START,
+            max_wallet_tokens: 100,
+            wallet_cap_duration: 60,
+            wallet_cap_supply: 1_000,
+            max_buy_tokens: 10,
+            max_buy_growth: 2,
+        }
+    }
+
+    #[test]
+    fn default_settings_never_block_a_buy() {
+        let launch = LaunchSettings::default();
+        assert!(launch.check_buy(0, 0, u64::MAX - 1, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn buys_wait_for_the_trading_start() {
+        let launch = settings();
+        assert!(launch.check_buy(START - 1, 0, 0, 1).is_err());
+        assert!(launch.check_buy(START, 0, 0, 1).is_ok());
+    }
+
+    #[test]
+    fn buy_cap_rises_with_time() {
+        let launch = settings();
+        assert_eq!(launch.max_buy_at(START - 10), 10);
+        assert_eq!(launch.max_buy_at(START), 10);
+        assert_eq!(launch.max_buy_at(START + 5), 20);
+        assert!(launch.check_buy(START, 0, 0, 11).is_err());
+        assert!(launch.check_buy(START + 1, 0, 0, 12).is_ok());
+    }
+
+    #[test]
+    fn wallet_cap_lifts_after_both_windows() {
+        let launch = settings();
+        assert!(launch.wallet_cap_active(START + 59, 1_000));
+        assert!(launch.wallet_cap_active(START + 60, 999));
+        assert!(!launch.wallet_cap_active(START + 60, 1_000));
+
+        assert!(launch.check_buy(START + 50, 0, 95, 5).is_ok());
+        assert!(launch.check_buy(START + 50, 0, 96, 5).is_err());
+        assert!(launch.check_buy(START + 60, 1_000, 96, 5).is_ok());
+    }
+
+    #[test]
+    fn resolve_opens_past_starts_at_launch() {
+        let launch = LaunchSettings::default()
+            .resolve(START, START + 10)
+            .unwrap();
+        assert_eq!(launch.trading_start_time, START);
+        assert!(settings().resolve(0, START).is_err());
+    }
+}
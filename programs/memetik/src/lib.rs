@@ -1,22 +1,35 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::instruction::Instruction;
-use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program::invoke_signed;
 use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program::{
+    allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+};
 use anchor_spl::{
-    associated_token::AssociatedToken,
+    associated_token::{self, get_associated_token_address, AssociatedToken},
     metadata::{
         create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3,
         Metadata as Metaplex,
     },
-    token::{self, mint_to, Burn, Mint, MintTo, Token, TokenAccount},
+    token::{self, spl_token::native_mint, sync_native, SyncNative, Token},
+    token_2022::{self, spl_token_2022::extension::ExtensionType},
+    token_interface::{
+        self, initialize_mint2, metadata_pointer_initialize, mint_to,
+        spl_token_metadata_interface::state::TokenMetadata, token_metadata_initialize,
+        transfer_fee_initialize, Burn, InitializeMint2, MetadataPointerInitialize, Mint, MintTo,
+        TokenAccount, TokenInterface, TokenMetadataInitialize, TransferFeeInitialize,
+    },
 };
+use raydium_cp_swap::states::{ObservationState, PoolState};
 
 pub const OBSERVATION_SEED: &str = "observation";
 pub const POOL_SEED: &str = "pool";
+pub const AMM_CONFIG_SEED: &str = "amm_config";
 pub const POOL_LP_MINT_SEED: &str = "pool_lp_mint";
 pub const POOL_VAULT_SEED: &str = "pool_vault";
 pub const AUTH_SEED: &str = "vault_and_lp_mint_auth_seed";
-pub const RAYDIUM_SP_SWAP_DEVNET: &str = "CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW";
+// discriminator + bump + disable_create_pool + index + trade/protocol/fund fee rates
+pub const AMM_CONFIG_CREATE_POOL_FEE_OFFSET: usize = 8 + 1 + 1 + 2 + 8 * 3;
 
 pub mod create_pool_fee_reveiver {
     use anchor_lang::prelude::declare_id;
@@ -28,10 +41,14 @@ pub mod create_pool_fee_reveiver {
 
 use bonding_curve::*;
 use constants::*;
+use events::*;
+use launch::*;
 use utils::*;
 
 mod bonding_curve;
 mod constants;
+mod events;
+mod launch;
 mod utils;
 
 // CPI HELPERS
@@ -61,11 +78,6 @@ fn create_cpi_instruction(
         data,
     }
 }
-fn invoke_cpi_instruction(instruction: Instruction, account_infos: &[AccountInfo]) -> Result<()> {
-    invoke(&instruction, account_infos)?;
-    Ok(())
-}
-
 fn invoke_signed_cpi_instruction(
     instruction: Instruction,
     account_infos: &[AccountInfo],
@@ -86,69 +98,163 @@ declare_id!("14a3y3QApSRvxd8kgG9S4FTjQFeTQ92XpUxTvXkTrknR");
 
 #[program]
 pub mod memetik {
-    use std::str::FromStr;
-
     use super::*;
 
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<GlobalConfig> {
+        let config = &mut ctx.accounts.config;
+
+        // start from the compiled in defaults, the admin tunes them with `update_config`
+        config.admin = *ctx.accounts.admin.to_account_info().key;
+        config.protocol_fee_receiver = config.admin;
+        config.escrow_amount = REQUIRED_ESCROW_AMOUNT;
+        config.maturity_duration = DAYS_TO_MATURITY * SECONDS_IN_A_DAY;
+        config.min_maturity_duration = MIN_MATURITY_DURATION;
+        config.max_maturity_duration = MAX_MATURITY_DURATION;
+        config.maturity_balance = REQUIRED_POOL_BALANCE_TO_MATURE;
+        config.min_tok_price = MIN_TOK_PRICE;
+        config.protocol_fee_bps = PROTOCOL_FEE_BPS;
+        config.creator_fee_bps = CREATOR_FEE_BPS;
+        config.forfeit_failed_escrow = FORFEIT_FAILED_ESCROW;
+        config.unique_tickers = UNIQUE_TICKERS;
+        config.lp_policy = DEFAULT_LP_POLICY;
+        // cp-swap's first fee tier
+        config.amm_config = Pubkey::find_program_address(
+            &[AMM_CONFIG_SEED.as_bytes(), &0u16.to_be_bytes()],
+            &raydium_cp_swap::id(),
+        )
+        .0;
+        config.pool_count = 0;
+        config.validate()?;
+
+        Ok(config.clone().into_inner())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, args: ConfigArgs) -> Result<GlobalConfig> {
+        let config = &mut ctx.accounts.config;
+
+        require!(
+            config.admin == *ctx.accounts.admin.to_account_info().key,
+            Error::NotConfigAdmin
+        );
+
+        if let Some(admin) = args.admin {
+            config.admin = admin;
+        }
+        if let Some(protocol_fee_receiver) = args.protocol_fee_receiver {
+            config.protocol_fee_receiver = protocol_fee_receiver;
+        }
+        if let Some(escrow_amount) = args.escrow_amount {
+            config.escrow_amount = escrow_amount;
+        }
+        if let Some(maturity_duration) = args.maturity_duration {
+            config.maturity_duration = maturity_duration;
+        }
+        if let Some(min_maturity_duration) = args.min_maturity_duration {
+            config.min_maturity_duration = min_maturity_duration;
+        }
+        if let Some(max_maturity_duration) = args.max_maturity_duration {
+            config.max_maturity_duration = max_maturity_duration;
+        }
+        if let Some(maturity_balance) = args.maturity_balance {
+            config.maturity_balance = maturity_balance;
+        }
+        if let Some(min_tok_price) = args.min_tok_price {
+            config.min_tok_price = min_tok_price;
+        }
+        if let Some(protocol_fee_bps) = args.protocol_fee_bps {
+            config.protocol_fee_bps = protocol_fee_bps;
+        }
+        if let Some(creator_fee_bps) = args.creator_fee_bps {
+            config.creator_fee_bps = creator_fee_bps;
+        }
+        if let Some(forfeit_failed_escrow) = args.forfeit_failed_escrow {
+            config.forfeit_failed_escrow = forfeit_failed_escrow;
+        }
+        if let Some(unique_tickers) = args.unique_tickers {
+            config.unique_tickers = unique_tickers;
+        }
+        if let Some(lp_policy) = args.lp_policy {
+            config.lp_policy = lp_policy;
+        }
+        if let Some(amm_config) = args.amm_config {
+            config.amm_config = amm_config;
+        }
+        config.validate()?;
+
+        Ok(config.clone().into_inner())
+    }
+
+    // holds `ticker` for `reserved_for`, passing the default key releases it
+    pub fn reserve_ticker(
+        ctx: Context<ReserveTicker>,
+        ticker: String,
+        reserved_for: Pubkey,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.config.admin == *ctx.accounts.admin.to_account_info().key,
+            Error::NotConfigAdmin
+        );
+        require!(check_valid_ticker(&ticker), Error::InvalidTickerFormat);
+        ctx.accounts.ticker_record.reserved_for = reserved_for;
+        Ok(())
+    }
+
     pub fn initialize(ctx: Context<Initialize>, token_info: TokenArgs) -> Result<Pool> {
         let creator = &ctx.accounts.signer;
-        let pool = &mut ctx.accounts.pool;
-        let escrow = &mut ctx.accounts.escrow;
+        let config = &ctx.accounts.config;
 
         require!(
             check_valid_ticker(&token_info.symbol),
             Error::InvalidTickerFormat
         );
+        // pools are keyed by index, the registry only enforces reservations and the uniqueness policy
+        let ticker_record = &mut ctx.accounts.ticker_record;
+        if ticker_record.reserved_for != Pubkey::default() {
+            require_keys_eq!(
+                ticker_record.reserved_for,
+                creator.key(),
+                Error::TickerReserved
+            );
+            ticker_record.reserved_for = Pubkey::default();
+        }
+        require!(
+            !config.unique_tickers || ticker_record.pool == Pubkey::default(),
+            Error::TickerTaken
+        );
+        ticker_record.pool = ctx.accounts.pool.key();
+        let curve = token_info.curve.unwrap_or_default();
+        curve.curve().validate()?;
+        let tok_price = calculate_spot_price(&curve, 0)?;
+        require!(
+            tok_price >= config.min_tok_price,
+            Error::InvalidCurveParameters
+        );
+        let total_supply = token_info.total_supply.unwrap_or(DEFAULT_TOTAL_SUPPLY);
+        let curve_supply = token_info.curve_supply.unwrap_or(DEFAULT_CURVE_SUPPLY);
+        // the LP reserve seeds the AMM, so the curve can never sell the whole supply
+        require!(
+            curve_supply > 0
+                && curve_supply < total_supply
+                && (total_supply - curve_supply) as u128 * BPS_DENOMINATOR as u128
+                    >= total_supply as u128 * MIN_LP_RESERVE_BPS as u128,
+            Error::InvalidSupply
+        );
+        // the curve has to price every token it can sell
+        calculate_spot_price(&curve, curve_supply)?;
 
         /////////////////////////////////
         // Create the token mint
         /////////////////////////////////
-        let seeds = &[
-            "mint".as_bytes(),
-            &token_info.symbol.as_bytes(),
-            &[ctx.bumps.mint],
-        ];
-        let signer = [&seeds[..]];
-
-        let token_data: DataV2 = DataV2 {
-            name: token_info.name,
-            symbol: token_info.symbol.clone(),
-            uri: token_info.uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        };
-        let metadata_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_metadata_program.to_account_info(),
-            CreateMetadataAccountsV3 {
-                payer: creator.to_account_info(),
-                update_authority: ctx.accounts.mint.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                metadata: ctx.accounts.metadata.to_account_info(),
-                mint_authority: ctx.accounts.mint.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
-                rent: ctx.accounts.rent.to_account_info(),
-            },
-            &signer,
-        );
-        let is_mutable = false;
-        let update_authority_is_signer = true;
-        let collection_details = None;
-        create_metadata_accounts_v3(
-            metadata_ctx,
-            token_data,
-            is_mutable,
-            update_authority_is_signer,
-            collection_details,
-        )?;
+        ctx.accounts.create_mint(&token_info, ctx.bumps.mint)?;
         msg!("Token mint created successfully.");
+        let pool = &mut ctx.accounts.pool;
+        let escrow = &mut ctx.accounts.escrow;
 
         /////////////////////////////////
         // Transfer SOL into pool escrow
         /////////////////////////////////
         let transfer_instruction =
-            system_instruction::transfer(&creator.key(), &escrow.key(), REQUIRED_ESCROW_AMOUNT);
+            system_instruction::transfer(&creator.key(), &escrow.key(), config.escrow_amount);
         let system_program = ctx.accounts.system_program.as_ref();
         anchor_lang::solana_program::program::invoke_signed(
             &transfer_instruction,
@@ -161,192 +267,246 @@ pub mod memetik {
         )?;
         msg!("SOL transferred intoto escrow successfully");
 
+        // the vault starts rent exempt so buys of any size can land in it
+        let vault_rent = Rent::get()?
+            .minimum_balance(0)
+            .saturating_sub(ctx.accounts.sol_vault.lamports());
+        if vault_rent > 0 {
+            anchor_lang::solana_program::program::invoke_signed(
+                &system_instruction::transfer(
+                    &creator.key(),
+                    &ctx.accounts.sol_vault.key(),
+                    vault_rent,
+                ),
+                &[
+                    creator.to_account_info(),
+                    ctx.accounts.sol_vault.to_account_info(),
+                    system_program.to_account_info(),
+                ],
+                &[],
+            )?;
+        }
+
         // init escrow
         escrow.pool = *pool.to_account_info().key;
         escrow.mint = *ctx.accounts.mint.to_account_info().key;
         escrow.owner = *creator.to_account_info().key;
-        escrow.balance = REQUIRED_ESCROW_AMOUNT;
+        escrow.balance = config.escrow_amount;
+        escrow.forfeit_on_failure = config.forfeit_failed_escrow;
 
         // init pool
-        let maturity_time_timestamp = calculate_test_time(); // time the pool has to reach milestone (maturity)
+        let maturity_duration = token_info
+            .maturity_duration
+            .unwrap_or(config.maturity_duration);
+        require!(
+            (config.min_maturity_duration..=config.max_maturity_duration)
+                .contains(&maturity_duration),
+            Error::InvalidMaturityDuration
+        );
+        let maturity_time_timestamp = calculate_maturity_time(maturity_duration); // time the pool has to reach milestone (maturity)
+        pool.index = config.pool_count;
         pool.creator = *creator.to_account_info().key;
         pool.mint = *ctx.accounts.mint.to_account_info().key;
         pool.ticker = token_info.symbol;
-        pool.tok_price = MIN_TOK_PRICE as u64;
+        pool.tok_price = tok_price;
+        pool.curve = curve;
         pool.maturity_time = maturity_time_timestamp;
-        pool.has_matured = false;
+        pool.status = PoolStatus::Active;
+        pool.real_sol_reserves = 0;
+        pool.real_token_reserves = 0;
+        pool.total_supply = total_supply;
+        pool.curve_supply = curve_supply;
+        pool.bump = ctx.bumps.pool;
+        pool.escrow_bump = ctx.bumps.escrow;
+        pool.mint_bump = ctx.bumps.mint;
+        pool.sol_vault_bump = ctx.bumps.sol_vault;
+        pool.treasury_bump = ctx.bumps.treasury;
+        pool.creator_vault_bump = ctx.bumps.creator_vault;
+        // the AMM creator only shows up at graduation, so its bump is found once here
+        let (_, amm_creator_bump) =
+            Pubkey::find_program_address(&[b"amm-creator", pool.key().as_ref()], ctx.program_id);
+        pool.amm_creator_bump = amm_creator_bump;
+        pool.maturity_balance = config.maturity_balance;
+        pool.protocol_fee_bps = config.protocol_fee_bps;
+        pool.creator_fee_bps = config.creator_fee_bps;
+        pool.lp_policy = config.lp_policy;
+        pool.launch = token_info
+            .launch
+            .unwrap_or_default()
+            .resolve(Clock::get()?.unix_timestamp, maturity_time_timestamp)?;
+
+        emit!(PoolCreated {
+            pool: pool.key(),
+            mint: pool.mint,
+            creator: pool.creator,
+            ticker: pool.ticker.clone(),
+            curve: pool.curve,
+            tok_price: pool.tok_price,
+            escrow_amount: escrow.balance,
+            maturity_time: pool.maturity_time,
+            maturity_balance: pool.maturity_balance,
+            total_supply: pool.total_supply,
+            curve_supply: pool.curve_supply,
+            trading_start_time: pool.launch.trading_start_time,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        // the creator's initial buy lands before anyone else can trade, under the same launch caps
+        if let Some(initial_buy) = &token_info.initial_buy {
+            ctx.accounts.initial_buy(initial_buy)?;
+        }
+
+        let pool = ctx.accounts.pool.clone().into_inner();
+        let config = &mut ctx.accounts.config;
+        config.pool_count = config
+            .pool_count
+            .checked_add(1)
+            .ok_or(Error::MathOverflow)?;
 
-        Ok(pool.clone().into_inner())
+        Ok(pool)
     }
 
-    pub fn close(ctx: Context<Close>, ticker: String) -> Result<()> {
-        let closer = &ctx.accounts.creator;
-        let pool = &ctx.accounts.pool;
+    // permissionless, winds down a pool that missed its milestone by `maturity_time`, or matured
+    // and never graduated, so holders can claim refunds. Whoever cranks it, the escrow settles
+    // to its owner or the treasury.
+    pub fn close(ctx: Context<Close>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
         let escrow = &ctx.accounts.escrow;
         let escrow_sol_balance = escrow.get_lamports();
 
-        require!(pool.ticker == ticker, Error::InvalidPoolTicker);
-        require!(
-            pool.creator == *closer.to_account_info().key,
-            Error::NotPoolCreator
-        );
         require!(
             escrow.pool == *pool.to_account_info().key,
             Error::InvalidEscrowAccount
         );
-        require!(
-            escrow.owner == *closer.to_account_info().key,
-            Error::NotEscrowOwner
-        );
         msg!("pool maturity time: {}", pool.maturity_time);
         msg!("current timestamp: {}", Clock::get()?.unix_timestamp);
         require!(
             check_if_maturity_time_passed(pool.maturity_time),
             Error::PoolNotMatured
         );
+        // a pool that can pay for its AMM gets a grace period to graduate, after that it fails
+        // too, a cp-swap pool created ahead of graduation would otherwise lock its SOL for good
+        pool.require_on_curve()?;
+        require!(
+            !pool.can_graduate()
+                || check_if_maturity_time_passed(
+                    pool.maturity_time.saturating_add(GRADUATION_GRACE_PERIOD)
+                ),
+            Error::PoolAlreadyMatured
+        );
 
         require!(
             escrow_sol_balance > 0 && escrow.balance > 0 && escrow_sol_balance >= escrow.balance,
             Error::InsufficientFundsInEscrow
         );
 
+        pool.status = PoolStatus::Failed;
+        // holders claim their share of what the curve holds right now, fees withheld in the mint
+        // aren't anyone's to redeem so they don't take a share
+        pool.failed_supply = ctx.accounts.mint.supply
+            - withheld_transfer_fees(&ctx.accounts.mint.to_account_info())?;
+        pool.failed_balance = pool.real_sol_reserves;
+
+        // the escrow either goes to the treasury or back to the creator when it closes
+        let escrow_forfeited = if escrow.forfeit_on_failure {
+            escrow.balance
+        } else {
+            0
+        };
+        **escrow.to_account_info().try_borrow_mut_lamports()? -= escrow_forfeited;
+        **ctx
+            .accounts
+            .treasury
+            .to_account_info()
+            .try_borrow_mut_lamports()? += escrow_forfeited;
+        msg!("Escrow forfeited: {}", escrow_forfeited);
+
+        emit!(PoolClosed {
+            pool: pool.key(),
+            mint: pool.mint,
+            creator: pool.creator,
+            escrow_refunded: escrow_sol_balance - escrow_forfeited,
+            escrow_forfeited,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
-    pub fn buy(ctx: Context<Buy>, ticker: String, amount: u64) -> Result<Pool> {
+    pub fn buy(ctx: Context<Buy>, amount: u64, max_sol_cost: u64) -> Result<TradeResult> {
         require!(amount > 0, Error::MustBuyAtLeastOneToken);
 
-        let current_supply = ctx.accounts.mint.supply;
-        let (total_cost, latest_price_per_unit) = calculate_price(current_supply, amount, false);
-
-        // Transfer SOL to the pool
-        let transfer_instruction = system_instruction::transfer(
-            &ctx.accounts.buyer.to_account_info().key(),
-            &ctx.accounts.pool.to_account_info().key(),
-            total_cost,
+        let current_supply = ctx.accounts.pool.real_token_reserves;
+        let (total_cost, latest_price_per_unit) =
+            calculate_price(&ctx.accounts.pool.curve, current_supply, amount, false)?;
+        let trade = ctx
+            .accounts
+            .pool
+            .quote_trade(amount, total_cost, latest_price_per_unit);
+        require!(
+            trade.sol_amount + trade.total_fee() <= max_sol_cost,
+            Error::SlippageExceeded
         );
-        let system_program = ctx.accounts.system_program.as_ref();
-        anchor_lang::solana_program::program::invoke_signed(
-            &transfer_instruction,
-            &[
-                ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.pool.to_account_info(),
-                system_program.to_account_info(),
-            ],
-            &[],
-        )?;
-        msg!("SOL sent to pool successfully");
-
-        let seeds = &["mint".as_bytes(), ticker.as_bytes(), &[ctx.bumps.mint]];
-        let signer = [&seeds[..]];
-
-        // Mint the tokens to the buyer's account in atomic units
-        mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    authority: ctx.accounts.mint.to_account_info(),
-                    to: ctx.accounts.buyer_token_account.to_account_info(),
-                    mint: ctx.accounts.mint.to_account_info(),
-                },
-                &signer,
-            ),
-            amount,
-        )?;
-        msg!("Tokens minted to buyer successfully");
 
-        // Update the token price based on the new supply
-        ctx.accounts.pool.tok_price = latest_price_per_unit;
+        ctx.accounts.execute_buy(trade)
+    }
 
-        // check if pool has matured
-        let new_pool_balance = ctx.accounts.pool.to_account_info().lamports();
-        let has_reached_maturity_amount = check_if_maturity_amount_reached(new_pool_balance);
+    // spend up to `sol_in` lamports, fees included, on as many tokens as the curve sells for it
+    pub fn buy_exact_sol_in(
+        ctx: Context<Buy>,
+        sol_in: u64,
+        min_tokens_out: u64,
+    ) -> Result<TradeResult> {
+        let pool = &ctx.accounts.pool;
+        let curve_sol_in =
+            calculate_amount_before_fees(sol_in, pool.protocol_fee_bps + pool.creator_fee_bps);
+        let current_supply = pool.real_token_reserves;
+        let amount = calculate_buy_amount(&pool.curve, current_supply, curve_sol_in)?
+            .min(pool.curve_supply_left());
+        require!(amount > 0, Error::MustBuyAtLeastOneToken);
+        require!(amount >= min_tokens_out, Error::SlippageExceeded);
 
-        // https://github.com/raydium-io/raydium-amm
-        if has_reached_maturity_amount {
-            ctx.accounts.pool.has_matured = true;
-            /////////////////////////////////
-            // CPI TO RAYDIUM CP-SWAP
-            //////////////////////////////////
-            // let raydium_program_id: Pubkey = Pubkey::from_str(RAYDIUM_SP_SWAP_DEVNET).unwrap();
-            // let accounts_meta = vec![
-            //     AccountMeta::new(*ctx.accounts.pool_state.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.authority.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_0_mint.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_1_mint.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.lp_mint.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.creator_token_0.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.creator_token_1.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.creator_lp_token.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_0_vault.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_1_vault.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.create_pool_fee.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.observation_state.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_program.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_0_program.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.token_1_program.to_account_info().key, false),
-            //     AccountMeta::new(
-            //         *ctx.accounts.associated_token_program.to_account_info().key,
-            //         false,
-            //     ),
-            //     AccountMeta::new(*ctx.accounts.system_program.to_account_info().key, false),
-            //     AccountMeta::new(*ctx.accounts.rent.to_account_info().key, false),
-            // ];
-            // let now = Clock::get()?.unix_timestamp;
-            // let create_amm_args = CreateAMMArgs {
-            //     init_amount_0: current_supply,
-            //     init_amount_1: new_pool_balance,
-            //     open_time: now as u64,
-            // };
-            // let data = serialize_cpi_instruction_data("global", "initialize", create_amm_args);
-            // let cpi_ix = create_cpi_instruction(raydium_program_id, accounts_meta, data);
-            // let account_infos = vec![
-            //     ctx.accounts.pool_state.to_account_info(),
-            //     ctx.accounts.authority.to_account_info(),
-            //     ctx.accounts.token_0_mint.to_account_info(),
-            //     ctx.accounts.token_1_mint.to_account_info(),
-            //     ctx.accounts.lp_mint.to_account_info(),
-            //     ctx.accounts.creator_token_0.to_account_info(),
-            //     ctx.accounts.creator_token_1.to_account_info(),
-            //     ctx.accounts.creator_lp_token.to_account_info(),
-            //     ctx.accounts.token_0_vault.to_account_info(),
-            //     ctx.accounts.token_1_vault.to_account_info(),
-            //     ctx.accounts.create_pool_fee.to_account_info(),
-            //     ctx.accounts.observation_state.to_account_info(),
-            //     ctx.accounts.token_program.to_account_info(),
-            //     ctx.accounts.token_0_program.to_account_info(),
-            //     ctx.accounts.token_1_program.to_account_info(),
-            //     ctx.accounts.associated_token_program.to_account_info(),
-            //     ctx.accounts.system_program.to_account_info(),
-            //     ctx.accounts.rent.to_account_info(),
-            // ];
-            // invoke_cpi_instruction(cpi_ix, &account_infos)?;
-        }
+        // rounding leaves the cost of `amount` at or just under `curve_sol_in`
+        let (total_cost, latest_price_per_unit) =
+            calculate_price(&pool.curve, current_supply, amount, false)?;
+        let trade = pool.quote_trade(amount, total_cost, latest_price_per_unit);
 
-        Ok(ctx.accounts.pool.clone().into_inner())
+        ctx.accounts.execute_buy(trade)
     }
 
-    pub fn sell(ctx: Context<Sell>, _ticker: String, amount: u64) -> Result<Pool> {
+    pub fn sell(ctx: Context<Sell>, amount: u64, min_sol_out: u64) -> Result<TradeResult> {
+        ctx.accounts.pool.require_active()?;
         require!(amount > 0, Error::NoTokensToSell);
         require!(
             ctx.accounts.seller_token_account.amount >= amount,
             Error::NoTokensToSell
         );
 
-        let current_supply = ctx.accounts.mint.supply;
-        let (sol_to_receive, latest_price_per_unit) = calculate_price(current_supply, amount, true);
+        let current_supply = ctx.accounts.pool.real_token_reserves;
+        let (sol_to_receive, latest_price_per_unit) =
+            calculate_price(&ctx.accounts.pool.curve, current_supply, amount, true)?;
+        let trade = ctx
+            .accounts
+            .pool
+            .quote_trade(amount, sol_to_receive, latest_price_per_unit);
+        // fees come out of the seller's proceeds
+        let seller_proceeds = sol_to_receive - trade.total_fee();
+        require!(seller_proceeds >= min_sol_out, Error::SlippageExceeded);
 
-        // check if pool has enough funds to buy token from seller
-        let min_pool_rent = 8 + std::mem::size_of::<Pool>() as u64;
+        // the curve pays out of its reserves and never dips into the vault's rent
+        let min_vault_rent = Rent::get()?.minimum_balance(0);
+        let real_sol_reserves = ctx
+            .accounts
+            .pool
+            .real_sol_reserves
+            .checked_sub(sol_to_receive)
+            .ok_or(Error::PoolInsufficientFunds)?;
         require!(
-            ctx.accounts.pool.to_account_info().lamports() >= (sol_to_receive + min_pool_rent),
+            ctx.accounts.sol_vault.lamports() >= (sol_to_receive + min_vault_rent),
             Error::PoolInsufficientFunds
         );
 
-        msg!(
-            "Pool bal {}",
-            ctx.accounts.pool.to_account_info().lamports()
-        );
+        msg!("Vault bal {}", ctx.accounts.sol_vault.lamports());
 
         // Burn the tokens from the seller's token account
         let cpi_accounts = Burn {
@@ -356,288 +516,1394 @@ pub mod memetik {
         };
         let cpi_context =
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        token::burn(cpi_context, amount)?;
+        token_interface::burn(cpi_context, amount)?;
         msg!("Tokens burned successfully");
 
-        // Transfer SOL from the pool to the seller
-        **ctx
-            .accounts
-            .pool
-            .to_account_info()
-            .try_borrow_mut_lamports()? -= sol_to_receive;
-        **ctx
-            .accounts
-            .seller
-            .to_account_info()
-            .try_borrow_mut_lamports()? += sol_to_receive;
+        // Transfer SOL from the vault to the seller and the fee accounts
+        for (recipient, lamports) in [
+            (ctx.accounts.seller.to_account_info(), seller_proceeds),
+            (ctx.accounts.treasury.to_account_info(), trade.protocol_fee),
+            (
+                ctx.accounts.creator_vault.to_account_info(),
+                trade.creator_fee,
+            ),
+        ] {
+            transfer_from_sol_vault(
+                &ctx.accounts.sol_vault,
+                &recipient,
+                &ctx.accounts.system_program,
+                &ctx.accounts.pool.key(),
+                ctx.accounts.pool.sol_vault_bump,
+                lamports,
+            )?;
+        }
+        ctx.accounts.pool.real_sol_reserves = real_sol_reserves;
+        ctx.accounts.pool.real_token_reserves = current_supply - amount;
 
         msg!("SOL transferred to seller successfully.");
 
         msg!(
-            "Pool balance after sell: {}",
-            ctx.accounts.pool.to_account_info().lamports()
+            "Vault balance after sell: {}",
+            ctx.accounts.sol_vault.lamports()
         );
 
         // Update the token price based on the new supply
         ctx.accounts.pool.tok_price = latest_price_per_unit;
+        ctx.accounts.position.record_sell(
+            ctx.accounts.pool.key(),
+            ctx.accounts.seller.key(),
+            &trade,
+            Clock::get()?.slot,
+        )?;
 
-        Ok(ctx.accounts.pool.clone().into_inner())
+        emit!(Trade {
+            pool: ctx.accounts.pool.key(),
+            mint: ctx.accounts.mint.key(),
+            trader: ctx.accounts.seller.key(),
+            side: TradeSide::Sell,
+            token_amount: trade.token_amount,
+            sol_amount: trade.sol_amount,
+            protocol_fee: trade.protocol_fee,
+            creator_fee: trade.creator_fee,
+            tok_price: trade.tok_price,
+            supply: ctx.accounts.mint.supply - amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(trade)
     }
-    pub fn get_pool(ctx: Context<GetPool>, ticker: String) -> Result<Pool> {
+
+    // burns the holder's whole balance of a failed pool's mint for their pro-rata share of the
+    // pool balance snapshotted at failure. Claiming again with nothing left pays nothing.
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<u64> {
         let pool = &ctx.accounts.pool;
-        require!(pool.ticker == ticker, Error::InvalidPoolTicker);
-        Ok(pool.clone().into_inner())
-    }
-}
+        require!(
+            matches!(pool.status, PoolStatus::Failed | PoolStatus::Closed),
+            Error::PoolNotFailed
+        );
 
-#[derive(Accounts)]
-#[instruction(token_info: TokenArgs)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        init,
-        payer = signer,
-        seeds = [b"pool", token_info.symbol.as_bytes()],
-        bump,
-        space = 8 + std::mem::size_of::<Pool>()
-    )]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        init,
-        payer = signer,
-        seeds = [b"pool-escrow", token_info.symbol.as_bytes()],
-        bump,
-        space = 8 + std::mem::size_of::<PoolEscrow>()
-    )]
-    pub escrow: Account<'info, PoolEscrow>,
-    /// CHECK: New Metaplex Account being created
-    #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
-    #[account(
-        init,
-        seeds = [b"mint", token_info.symbol.as_bytes()],
-        bump,
-        payer = signer,
-        mint::decimals = DEFAULT_TOKEN_DECIMALS,
-        mint::authority = mint,
-    )]
-    pub mint: Account<'info, Mint>,
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub token_metadata_program: Program<'info, Metaplex>,
-}
+        let amount = ctx.accounts.holder_token_account.amount;
+        if amount == 0 {
+            return Ok(0);
+        }
+        // the creator can withdraw withheld fees into their own account and claim on them, so a
+        // refund never takes more than the pool has left
+        let refund = calculate_refund(amount, pool.failed_supply, pool.failed_balance)
+            .min(pool.real_sol_reserves);
 
-#[derive(Accounts)]
-#[instruction(ticker: String)]
-pub struct Close<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [b"pool", ticker.as_bytes()],
-        bump,
-    )]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        seeds = [b"pool-escrow", ticker.as_bytes()],
-        bump,
-        close = creator
-    )]
-    pub escrow: Account<'info, PoolEscrow>,
-    pub system_program: Program<'info, System>,
-}
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
-#[derive(Accounts)]
-#[instruction(ticker: String)]
-pub struct Buy<'info> {
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [b"pool", ticker.as_bytes()],
-        bump,
-    )]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        seeds = [b"mint", ticker.as_bytes()],
-        bump,
-        mint::authority = mint,
-    )]
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = mint,
-        associated_token::authority = buyer,
-    )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+        transfer_from_sol_vault(
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.holder,
+            &ctx.accounts.system_program,
+            &ctx.accounts.pool.key(),
+            ctx.accounts.pool.sol_vault_bump,
+            refund,
+        )?;
+        let pool = &mut ctx.accounts.pool;
+        pool.real_sol_reserves = pool.real_sol_reserves.saturating_sub(refund);
+        pool.real_token_reserves = pool.real_token_reserves.saturating_sub(amount);
+        msg!("Refunded {} lamports for {} tokens", refund, amount);
 
-    // /////////////////////////////////
-    // // AMM CREATION ACCOUNTS
-    // /////////////////////////////////
-    /// Which config the pool belongs to.
-    /// CHECK fwer
-    pub cp_swap_program: UncheckedAccount<'info>,
-    /// CHECK fine
-    pub amm_config: UncheckedAccount<'info>,
+        // the last holder to claim closes the pool, withheld fees are all that can be left
+        let withheld = withheld_transfer_fees(&ctx.accounts.mint.to_account_info())?;
+        if ctx.accounts.mint.supply - amount <= withheld {
+            ctx.accounts.pool.status = PoolStatus::Closed;
+        }
 
-    /// CHECK: pool vault and lp mint authority
-    #[account(
-        seeds = [
-            raydium_cp_swap::AUTH_SEED.as_bytes(),
-        ],
-        seeds::program = cp_swap_program.key(),
-        bump,
-    )]
-    pub authority: UncheckedAccount<'info>,
+        emit!(RefundClaimed {
+            pool: ctx.accounts.pool.key(),
+            mint: ctx.accounts.mint.key(),
+            holder: ctx.accounts.holder.key(),
+            token_amount: amount,
+            sol_amount: refund,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    /// CHECK: Initialize an account to store the pool state, init by cp-swap
-    #[account(
-        mut,
-        seeds = [
-            POOL_SEED.as_bytes(),
-            amm_config.key().as_ref(),
-            mint.key().as_ref(),
-            token_1_mint.key().as_ref(),
-        ],
-        seeds::program = cp_swap_program.key(),
-        bump,
+        Ok(refund)
+    }
+
+    // permissionless, sweeps the protocol fees to the config's fee receiver
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>) -> Result<u64> {
+        let claimable = claimable_fees(&ctx.accounts.treasury.to_account_info())?;
+        **ctx
+            .accounts
+            .treasury
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= claimable;
+        **ctx
+            .accounts
+            .protocol_fee_receiver
+            .to_account_info()
+            .try_borrow_mut_lamports()? += claimable;
+        msg!("Claimed {} lamports of protocol fees", claimable);
+
+        Ok(claimable)
+    }
+
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<u64> {
+        let creator = &ctx.accounts.creator;
+        let pool = &ctx.accounts.pool;
+
+        require!(
+            pool.creator == *creator.to_account_info().key,
+            Error::NotPoolCreator
+        );
+
+        let claimable = claimable_fees(&ctx.accounts.creator_vault.to_account_info())?;
+        **ctx
+            .accounts
+            .creator_vault
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= claimable;
+        **creator.to_account_info().try_borrow_mut_lamports()? += claimable;
+        msg!("Claimed {} lamports of creator fees", claimable);
+
+        Ok(claimable)
+    }
+
+    // https://github.com/raydium-io/raydium-cp-swap
+    pub fn graduate(ctx: Context<Graduate>) -> Result<Pool> {
+        ctx.accounts.pool.require_on_curve()?;
+        let pool_balance = ctx.accounts.pool.real_sol_reserves;
+        require!(ctx.accounts.pool.is_complete(), Error::PoolNotMatured);
+        // a curve that sold out cheaply can't pay for the AMM, it fails once it expires instead
+        require!(
+            pool_balance >= MIN_MATURITY_BALANCE,
+            Error::PoolInsufficientFunds
+        );
+        let pool_key = ctx.accounts.pool.key();
+        let seeds = &[
+            "mint".as_bytes(),
+            pool_key.as_ref(),
+            &[ctx.accounts.pool.mint_bump],
+        ];
+        let signer = [&seeds[..]];
+
+        let amm_creator_seeds = &[
+            "amm-creator".as_bytes(),
+            pool_key.as_ref(),
+            &[ctx.accounts.pool.amm_creator_bump],
+        ];
+        let amm_creator_signer = [&amm_creator_seeds[..]];
+
+        let rent = Rent::get()?;
+        let pool_sol = pool_balance;
+        // the AMM creator pays for every account cp-swap creates plus the pool creation fee
+        let amm_accounts_rent = rent.minimum_balance(PoolState::LEN)
+            + rent.minimum_balance(ObservationState::LEN)
+            + rent.minimum_balance(token::Mint::LEN)
+            + rent.minimum_balance(token_account_len(&ctx.accounts.mint.to_account_info())?) * 2
+            + rent.minimum_balance(token::TokenAccount::LEN) * 3;
+        let create_pool_fee =
+            read_amm_config_create_pool_fee(&ctx.accounts.amm_config.try_borrow_data()?)
+                .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        let wsol_amount = pool_sol
+            .checked_sub(amm_accounts_rent + create_pool_fee)
+            .ok_or(Error::PoolInsufficientFunds)?;
+        let token_amount =
+            calculate_liquidity_token_amount(wsol_amount, ctx.accounts.pool.tok_price);
+        // a Token-2022 transfer fee is withheld on the way into the AMM vault, so mint enough for
+        // the vault to still receive `token_amount` at the curve's last price
+        let token_amount =
+            amount_before_transfer_fee(&ctx.accounts.mint.to_account_info(), token_amount)?;
+        // the AMM is seeded out of the LP reserve, so the mint never passes its total supply. A
+        // short reserve shrinks the SOL side with it, so the AMM still opens at the curve's last
+        // price and the SOL it doesn't take stays in the pool
+        let lp_reserve = ctx.accounts.pool.lp_reserve_supply();
+        let (token_amount, wsol_amount) = if token_amount > lp_reserve {
+            let wsol_amount =
+                (wsol_amount as u128 * lp_reserve as u128 / token_amount as u128) as u64;
+            (lp_reserve, wsol_amount)
+        } else {
+            (token_amount, wsol_amount)
+        };
+
+        /////////////////////////////////
+        // Move curve SOL to the AMM creator
+        /////////////////////////////////
+        // only what the AMM takes moves, the vault keeps its rent
+        let amm_sol = amm_accounts_rent + create_pool_fee + wsol_amount;
+        transfer_from_sol_vault(
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.amm_creator,
+            &ctx.accounts.system_program,
+            &pool_key,
+            ctx.accounts.pool.sol_vault_bump,
+            amm_sol,
+        )?;
+        ctx.accounts.pool.real_sol_reserves = pool_sol - amm_sol;
+
+        /////////////////////////////////
+        // Seed the AMM creator token accounts
+        /////////////////////////////////
+        for (associated_token, token_mint, token_program) in [
+            (
+                ctx.accounts.amm_creator_token_account.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ),
+            (
+                ctx.accounts.amm_creator_wsol_account.to_account_info(),
+                ctx.accounts.wsol_mint.to_account_info(),
+                ctx.accounts.wsol_token_program.to_account_info(),
+            ),
+        ] {
+            associated_token::create_idempotent(CpiContext::new_with_signer(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.amm_creator.to_account_info(),
+                    associated_token,
+                    authority: ctx.accounts.amm_creator.to_account_info(),
+                    mint: token_mint,
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program,
+                },
+                &amm_creator_signer,
+            ))?;
+        }
+
+        // wrap the curve SOL
+        let transfer_instruction = system_instruction::transfer(
+            &ctx.accounts.amm_creator.key(),
+            &ctx.accounts.amm_creator_wsol_account.key(),
+            wsol_amount,
+        );
+        invoke_signed(
+            &transfer_instruction,
+            &[
+                ctx.accounts.amm_creator.to_account_info(),
+                ctx.accounts.amm_creator_wsol_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &amm_creator_signer,
+        )?;
+        sync_native(CpiContext::new(
+            ctx.accounts.wsol_token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.amm_creator_wsol_account.to_account_info(),
+            },
+        ))?;
+
+        // mint the token side of the liquidity at the curve's last price
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    authority: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.amm_creator_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+                &signer,
+            ),
+            token_amount,
+        )?;
+        msg!(
+            "AMM liquidity prepared: {} lamports, {} tokens",
+            wsol_amount,
+            token_amount
+        );
+
+        /////////////////////////////////
+        // CPI TO RAYDIUM CP-SWAP
+        //////////////////////////////////
+        // cp-swap requires token_0 mint < token_1 mint
+        let token_side = (
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.amm_creator_token_account.to_account_info(),
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            token_amount,
+        );
+        let wsol_side = (
+            ctx.accounts.wsol_mint.to_account_info(),
+            ctx.accounts.amm_creator_wsol_account.to_account_info(),
+            ctx.accounts.wsol_vault.to_account_info(),
+            ctx.accounts.wsol_token_program.to_account_info(),
+            wsol_amount,
+        );
+        let (
+            (token_0_mint, creator_token_0, token_0_vault, token_0_program, init_amount_0),
+            (token_1_mint, creator_token_1, token_1_vault, token_1_program, init_amount_1),
+        ) = if ctx.accounts.mint.key() < ctx.accounts.wsol_mint.key() {
+            (token_side, wsol_side)
+        } else {
+            (wsol_side, token_side)
+        };
+
+        let accounts_meta = vec![
+            AccountMeta::new(ctx.accounts.amm_creator.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.amm_config.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.authority.key(), false),
+            AccountMeta::new(ctx.accounts.pool_state.key(), false),
+            AccountMeta::new_readonly(token_0_mint.key(), false),
+            AccountMeta::new_readonly(token_1_mint.key(), false),
+            AccountMeta::new(ctx.accounts.lp_mint.key(), false),
+            AccountMeta::new(creator_token_0.key(), false),
+            AccountMeta::new(creator_token_1.key(), false),
+            AccountMeta::new(ctx.accounts.creator_lp_token.key(), false),
+            AccountMeta::new(token_0_vault.key(), false),
+            AccountMeta::new(token_1_vault.key(), false),
+            AccountMeta::new(ctx.accounts.create_pool_fee.key(), false),
+            AccountMeta::new(ctx.accounts.observation_state.key(), false),
+            // the lp mint is always a legacy token mint
+            AccountMeta::new_readonly(ctx.accounts.wsol_token_program.key(), false),
+            AccountMeta::new_readonly(token_0_program.key(), false),
+            AccountMeta::new_readonly(token_1_program.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.associated_token_program.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+        ];
+        let now = Clock::get()?.unix_timestamp;
+        let create_amm_args = CreateAMMArgs {
+            init_amount_0,
+            init_amount_1,
+            open_time: now as u64,
+        };
+        let data = serialize_cpi_instruction_data("global", "initialize", create_amm_args);
+        let cpi_ix =
+            create_cpi_instruction(ctx.accounts.cp_swap_program.key(), accounts_meta, data);
+        let account_infos = vec![
+            ctx.accounts.amm_creator.to_account_info(),
+            ctx.accounts.amm_config.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.pool_state.to_account_info(),
+            token_0_mint,
+            token_1_mint,
+            ctx.accounts.lp_mint.to_account_info(),
+            creator_token_0,
+            creator_token_1,
+            ctx.accounts.creator_lp_token.to_account_info(),
+            token_0_vault,
+            token_1_vault,
+            ctx.accounts.create_pool_fee.to_account_info(),
+            ctx.accounts.observation_state.to_account_info(),
+            ctx.accounts.wsol_token_program.to_account_info(),
+            token_0_program,
+            token_1_program,
+            ctx.accounts.associated_token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.rent.to_account_info(),
+            ctx.accounts.cp_swap_program.to_account_info(),
+        ];
+        invoke_signed_cpi_instruction(cpi_ix, &account_infos, &amm_creator_signer)?;
+        msg!("Pool graduated to cp-swap successfully");
+
+        /////////////////////////////////
+        // Settle the LP tokens
+        /////////////////////////////////
+        let lp_amount = token::accessor::amount(&ctx.accounts.creator_lp_token.to_account_info())?;
+        match ctx.accounts.pool.lp_policy {
+            LpPolicy::Burn => token_interface::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.wsol_token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        from: ctx.accounts.creator_lp_token.to_account_info(),
+                        authority: ctx.accounts.amm_creator.to_account_info(),
+                    },
+                    &amm_creator_signer,
+                ),
+                lp_amount,
+            )?,
+            LpPolicy::Lock => {}
+            LpPolicy::Vest { .. } => ctx.accounts.pool.lp_vesting_amount = lp_amount,
+        }
+        ctx.accounts.pool.lp_mint = ctx.accounts.lp_mint.key();
+        ctx.accounts.pool.graduated_at = now;
+        ctx.accounts.pool.status = PoolStatus::Graduated;
+
+        emit!(PoolGraduated {
+            pool: ctx.accounts.pool.key(),
+            mint: ctx.accounts.mint.key(),
+            amm_pool: ctx.accounts.pool_state.key(),
+            token_amount,
+            wsol_amount,
+            lp_amount,
+            lp_policy: ctx.accounts.pool.lp_policy,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(ctx.accounts.pool.clone().into_inner())
+    }
+
+    // releases the LP tokens vested to the creator so far
+    pub fn claim_lp_tokens(ctx: Context<ClaimLpTokens>) -> Result<u64> {
+        let pool = &ctx.accounts.pool;
+        require!(
+            pool.creator == ctx.accounts.creator.key(),
+            Error::NotPoolCreator
+        );
+        let LpPolicy::Vest { cliff, duration } = pool.lp_policy else {
+            return err!(Error::LpTokensNotVesting);
+        };
+
+        let vested = calculate_vested_amount(
+            pool.lp_vesting_amount,
+            pool.graduated_at.saturating_add(cliff),
+            duration,
+            Clock::get()?.unix_timestamp,
+        );
+        let claimable = vested - pool.lp_claimed;
+        let pool_key = pool.key();
+        let amm_creator_seeds = &[
+            "amm-creator".as_bytes(),
+            pool_key.as_ref(),
+            &[pool.amm_creator_bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.amm_creator_lp_token.to_account_info(),
+                    to: ctx.accounts.creator_lp_token.to_account_info(),
+                    authority: ctx.accounts.amm_creator.to_account_info(),
+                },
+                &[&amm_creator_seeds[..]],
+            ),
+            claimable,
+        )?;
+        ctx.accounts.pool.lp_claimed = vested;
+        msg!("Claimed {} vested LP tokens", claimable);
+
+        emit!(LpTokensClaimed {
+            pool: pool_key,
+            creator: ctx.accounts.creator.key(),
+            lp_mint: ctx.accounts.lp_mint.key(),
+            amount: claimable,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(claimable)
+    }
+
+    pub fn get_pool(ctx: Context<GetPool>) -> Result<Pool> {
+        Ok(ctx.accounts.pool.clone().into_inner())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    // only the upgrade authority can claim the admin seat
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::Memetik>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ Error::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + GlobalConfig::INIT_SPACE
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(ticker: String)]
+pub struct ReserveTicker<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, GlobalConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"ticker", ticker.as_bytes()],
+        bump,
+        space = 8 + TickerRecord::INIT_SPACE
+    )]
+    pub ticker_record: Account<'info, TickerRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_info: TokenArgs)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: Box<Account<'info, GlobalConfig>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        seeds = [b"ticker", token_info.symbol.as_bytes()],
+        bump,
+        space = 8 + TickerRecord::INIT_SPACE
+    )]
+    pub ticker_record: Account<'info, TickerRecord>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"pool", config.pool_count.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Pool::INIT_SPACE
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"pool-escrow", pool.key().as_ref()],
+        bump,
+        space = 8 + PoolEscrow::INIT_SPACE
+    )]
+    pub escrow: Box<Account<'info, PoolEscrow>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        seeds = [b"treasury"],
+        bump,
+        space = 8 + FeeVault::INIT_SPACE
+    )]
+    pub treasury: Account<'info, FeeVault>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"creator-vault", pool.key().as_ref()],
+        bump,
+        space = 8 + FeeVault::INIT_SPACE
+    )]
+    pub creator_vault: Account<'info, FeeVault>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump,
+        space = 8 + UserPosition::INIT_SPACE
+    )]
+    pub creator_position: Box<Account<'info, UserPosition>>,
+    // holds the curve reserves, system owned so it only ever carries lamports
+    #[account(
+        mut,
+        seeds = [b"sol-vault", pool.key().as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    /// CHECK: New Metaplex Account being created, only for legacy token mints
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: created in the handler, which extensions it gets depends on the token program
+    #[account(
+        mut,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: the creator's ATA, created in the handler once the mint exists, only for launches with an initial buy
+    #[account(mut)]
+    pub creator_token_account: Option<UncheckedAccount<'info>>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_metadata_program: Option<Program<'info, Metaplex>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'info> Initialize<'info> {
+    // Token-2022 mints carry their metadata in the mint itself, legacy mints go through Metaplex
+    fn create_mint(&self, token_info: &TokenArgs, mint_bump: u8) -> Result<()> {
+        let pool_key = self.pool.key();
+        let seeds = &["mint".as_bytes(), pool_key.as_ref(), &[mint_bump]];
+        let signer = [&seeds[..]];
+        let mint = self.mint.to_account_info();
+        let token_program = self.token_program.to_account_info();
+        let is_token_2022 = token_program.key() == token_2022::ID;
+
+        let mut extensions = vec![];
+        if is_token_2022 {
+            extensions.push(ExtensionType::MetadataPointer);
+        }
+        if let Some(transfer_fee) = &token_info.transfer_fee {
+            require!(is_token_2022, Error::TransferFeeRequiresToken2022);
+            require!(
+                transfer_fee.basis_points <= MAX_TRANSFER_FEE_BPS,
+                Error::InvalidTransferFee
+            );
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        let mint_len = token_interface::find_mint_account_size(Some(&extensions))?;
+        let token_metadata = TokenMetadata {
+            update_authority: Some(mint.key()).try_into()?,
+            mint: mint.key(),
+            name: token_info.name.clone(),
+            symbol: token_info.symbol.clone(),
+            uri: token_info.uri.clone(),
+            additional_metadata: vec![],
+        };
+        // token metadata reallocs the mint, so it has to be funded for it up front
+        let metadata_len = if is_token_2022 {
+            token_metadata.tlv_size_of()?
+        } else {
+            0
+        };
+
+        let system_program = self.system_program.to_account_info();
+        let rent = Rent::get()?.minimum_balance(mint_len + metadata_len);
+        if mint.lamports() == 0 {
+            create_account(
+                CpiContext::new_with_signer(
+                    system_program,
+                    CreateAccount {
+                        from: self.signer.to_account_info(),
+                        to: mint.clone(),
+                    },
+                    &signer,
+                ),
+                rent,
+                mint_len as u64,
+                token_program.key,
+            )?;
+        } else {
+            // the mint address is known before launch, so anyone can fund it and make
+            // create_account fail; top it up and claim it instead
+            let top_up = rent.saturating_sub(mint.lamports());
+            if top_up > 0 {
+                transfer(
+                    CpiContext::new(
+                        system_program.clone(),
+                        Transfer {
+                            from: self.signer.to_account_info(),
+                            to: mint.clone(),
+                        },
+                    ),
+                    top_up,
+                )?;
+            }
+            allocate(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    Allocate {
+                        account_to_allocate: mint.clone(),
+                    },
+                    &signer,
+                ),
+                mint_len as u64,
+            )?;
+            assign(
+                CpiContext::new_with_signer(
+                    system_program,
+                    Assign {
+                        account_to_assign: mint.clone(),
+                    },
+                    &signer,
+                ),
+                token_program.key,
+            )?;
+        }
+
+        // extensions have to be initialized before the mint itself
+        if is_token_2022 {
+            metadata_pointer_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    MetadataPointerInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                Some(mint.key()),
+                Some(mint.key()),
+            )?;
+        }
+        if let Some(transfer_fee) = &token_info.transfer_fee {
+            // the fee is fixed at launch, the creator collects what it withholds
+            transfer_fee_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    TransferFeeInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: mint.clone(),
+                    },
+                ),
+                None,
+                Some(self.signer.key),
+                transfer_fee.basis_points,
+                transfer_fee.maximum_fee,
+            )?;
+        }
+        initialize_mint2(
+            CpiContext::new(
+                token_program.clone(),
+                InitializeMint2 { mint: mint.clone() },
+            ),
+            DEFAULT_TOKEN_DECIMALS,
+            mint.key,
+            None,
+        )?;
+
+        if is_token_2022 {
+            token_metadata_initialize(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    TokenMetadataInitialize {
+                        token_program_id: token_program,
+                        metadata: mint.clone(),
+                        update_authority: mint.clone(),
+                        mint_authority: mint.clone(),
+                        mint,
+                    },
+                    &signer,
+                ),
+                token_metadata.name,
+                token_metadata.symbol,
+                token_metadata.uri,
+            )?;
+            return Ok(());
+        }
+
+        let (Some(metadata), Some(token_metadata_program)) =
+            (&self.metadata, &self.token_metadata_program)
+        else {
+            return err!(Error::MissingMetadataAccounts);
+        };
+        let token_data: DataV2 = DataV2 {
+            name: token_metadata.name,
+            symbol: token_metadata.symbol,
+            uri: token_metadata.uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        let metadata_ctx = CpiContext::new_with_signer(
+            token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                payer: self.signer.to_account_info(),
+                update_authority: mint.clone(),
+                mint: mint.clone(),
+                metadata: metadata.to_account_info(),
+                mint_authority: mint,
+                system_program: self.system_program.to_account_info(),
+                rent: self.rent.to_account_info(),
+            },
+            &signer,
+        );
+        let is_mutable = false;
+        let update_authority_is_signer = true;
+        let collection_details = None;
+        create_metadata_accounts_v3(
+            metadata_ctx,
+            token_data,
+            is_mutable,
+            update_authority_is_signer,
+            collection_details,
+        )
+    }
+
+    // the creator buys from zero supply before anyone else can see the pool
+    fn initial_buy(&mut self, initial_buy: &InitialBuyArgs) -> Result<()> {
+        require!(initial_buy.amount > 0, Error::MustBuyAtLeastOneToken);
+        self.pool.launch.check_buy(
+            Clock::get()?.unix_timestamp,
+            self.pool.real_token_reserves,
+            self.creator_position.tokens_bought,
+            initial_buy.amount,
+        )?;
+        let (Some(creator_token_account), Some(associated_token_program)) =
+            (&self.creator_token_account, &self.associated_token_program)
+        else {
+            return err!(Error::MissingInitialBuyAccounts);
+        };
+
+        let (total_cost, latest_price_per_unit) =
+            calculate_price(&self.pool.curve, 0, initial_buy.amount, false)?;
+        let trade = self
+            .pool
+            .quote_trade(initial_buy.amount, total_cost, latest_price_per_unit);
+        require!(
+            trade.sol_amount + trade.total_fee() <= initial_buy.max_sol_cost,
+            Error::SlippageExceeded
+        );
+
+        for (recipient, lamports) in [
+            (self.sol_vault.to_account_info(), trade.sol_amount),
+            (self.treasury.to_account_info(), trade.protocol_fee),
+            (self.creator_vault.to_account_info(), trade.creator_fee),
+        ] {
+            if lamports == 0 {
+                continue;
+            }
+            anchor_lang::solana_program::program::invoke_signed(
+                &system_instruction::transfer(&self.signer.key(), &recipient.key(), lamports),
+                &[
+                    self.signer.to_account_info(),
+                    recipient,
+                    self.system_program.to_account_info(),
+                ],
+                &[],
+            )?;
+        }
+
+        // the ATA program checks the address, so a wrong account fails here
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: self.signer.to_account_info(),
+                associated_token: creator_token_account.to_account_info(),
+                authority: self.signer.to_account_info(),
+                mint: self.mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))?;
+
+        let pool_key = self.pool.key();
+        let seeds = &["mint".as_bytes(), pool_key.as_ref(), &[self.pool.mint_bump]];
+        let signer = [&seeds[..]];
+        mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    authority: self.mint.to_account_info(),
+                    to: creator_token_account.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                },
+                &signer,
+            ),
+            trade.token_amount,
+        )?;
+        msg!("Initial buy minted to creator successfully");
+        let has_reached_maturity_amount = self.pool.record_buy(&trade)?;
+        self.creator_position.record_buy(
+            pool_key,
+            self.signer.key(),
+            &trade,
+            Clock::get()?.slot,
+        )?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(Trade {
+            pool: pool_key,
+            mint: self.mint.key(),
+            trader: self.signer.key(),
+            side: TradeSide::Buy,
+            token_amount: trade.token_amount,
+            sol_amount: trade.sol_amount,
+            protocol_fee: trade.protocol_fee,
+            creator_fee: trade.creator_fee,
+            tok_price: trade.tok_price,
+            supply: trade.token_amount,
+            timestamp,
+        });
+        if has_reached_maturity_amount {
+            emit!(PoolMatured {
+                pool: pool_key,
+                mint: self.mint.key(),
+                pool_balance: self.pool.real_sol_reserves,
+                timestamp,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    pub signer: Signer<'info>,
+    // escrow owner, gets back whatever the escrow doesn't forfeit
+    #[account(mut, address = escrow.owner @ Error::NotEscrowOwner)]
+    pub creator: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"pool-escrow", pool.key().as_ref()],
+        bump = pool.escrow_bump,
+        close = creator
+    )]
+    pub escrow: Account<'info, PoolEscrow>,
+    #[account(
+        seeds = [b"mint", pool.key().as_ref()],
+        bump = pool.mint_bump,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = pool.treasury_bump,
+    )]
+    pub treasury: Account<'info, FeeVault>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump = pool.mint_bump,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"sol-vault", pool.key().as_ref()],
+        bump = pool.sol_vault_bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump = pool.mint_bump,
+        mint::authority = mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
     )]
-    pub pool_state: UncheckedAccount<'info>,
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        seeds = [b"position", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+        space = 8 + UserPosition::INIT_SPACE
+    )]
+    pub position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = pool.treasury_bump,
+    )]
+    pub treasury: Account<'info, FeeVault>,
+    #[account(
+        mut,
+        seeds = [b"creator-vault", pool.key().as_ref()],
+        bump = pool.creator_vault_bump,
+    )]
+    pub creator_vault: Account<'info, FeeVault>,
+    #[account(
+        mut,
+        seeds = [b"sol-vault", pool.key().as_ref()],
+        bump = pool.sol_vault_bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Buy<'info> {
+    fn execute_buy(&mut self, trade: TradeResult) -> Result<TradeResult> {
+        self.pool.require_active()?;
+        require!(
+            !check_if_maturity_time_passed(self.pool.maturity_time),
+            Error::PoolExpired
+        );
+        self.pool.launch.check_buy(
+            Clock::get()?.unix_timestamp,
+            self.pool.real_token_reserves,
+            self.position.tokens_bought,
+            trade.token_amount,
+        )?;
+
+        // Transfer SOL to the vault and the fees to their accounts
+        for (recipient, lamports) in [
+            (self.sol_vault.to_account_info(), trade.sol_amount),
+            (self.treasury.to_account_info(), trade.protocol_fee),
+            (self.creator_vault.to_account_info(), trade.creator_fee),
+        ] {
+            if lamports == 0 {
+                continue;
+            }
+            let transfer_instruction = system_instruction::transfer(
+                &self.buyer.to_account_info().key(),
+                &recipient.key(),
+                lamports,
+            );
+            let system_program = self.system_program.as_ref();
+            anchor_lang::solana_program::program::invoke_signed(
+                &transfer_instruction,
+                &[
+                    self.buyer.to_account_info(),
+                    recipient,
+                    system_program.to_account_info(),
+                ],
+                &[],
+            )?;
+        }
+        msg!("SOL sent to vault successfully");
+
+        let pool_key = self.pool.key();
+        let seeds = &["mint".as_bytes(), pool_key.as_ref(), &[self.pool.mint_bump]];
+        let signer = [&seeds[..]];
+
+        // Mint the tokens to the buyer's account in atomic units
+        mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    authority: self.mint.to_account_info(),
+                    to: self.buyer_token_account.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                },
+                &signer,
+            ),
+            trade.token_amount,
+        )?;
+        msg!("Tokens minted to buyer successfully");
+        let has_reached_maturity_amount = self.pool.record_buy(&trade)?;
+        self.position.record_buy(
+            self.pool.key(),
+            self.buyer.key(),
+            &trade,
+            Clock::get()?.slot,
+        )?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(Trade {
+            pool: self.pool.key(),
+            mint: self.mint.key(),
+            trader: self.buyer.key(),
+            side: TradeSide::Buy,
+            token_amount: trade.token_amount,
+            sol_amount: trade.sol_amount,
+            protocol_fee: trade.protocol_fee,
+            creator_fee: trade.creator_fee,
+            tok_price: trade.tok_price,
+            supply: self.mint.supply + trade.token_amount,
+            timestamp,
+        });
+
+        if has_reached_maturity_amount {
+            emit!(PoolMatured {
+                pool: self.pool.key(),
+                mint: self.mint.key(),
+                pool_balance: self.pool.real_sol_reserves,
+                timestamp,
+            });
+        }
+
+        Ok(trade)
+    }
+}
 
-    // // /// Token_0 mint, the key must smaller then token_1 mint.
-    // #[account(
-    //     constraint = token_0_mint.key() < token_1_mint.key(),
-    //     mint::token_program = token_0_program,
-    // )]
-    // pub token_0_mint: Account<'info, Mint>,
+#[derive(Accounts)]
+pub struct Sell<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump = pool.mint_bump,
+        mint::authority = mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+    // tokens can arrive by transfer, so a seller may not have a position yet
+    #[account(
+        init_if_needed,
+        payer = seller,
+        seeds = [b"position", pool.key().as_ref(), seller.key().as_ref()],
+        bump,
+        space = 8 + UserPosition::INIT_SPACE
+    )]
+    pub position: Account<'info, UserPosition>,
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = pool.treasury_bump,
+    )]
+    pub treasury: Account<'info, FeeVault>,
+    #[account(
+        mut,
+        seeds = [b"creator-vault", pool.key().as_ref()],
+        bump = pool.creator_vault_bump,
+    )]
+    pub creator_vault: Account<'info, FeeVault>,
+    #[account(
+        mut,
+        seeds = [b"sol-vault", pool.key().as_ref()],
+        bump = pool.sol_vault_bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
 
-    // /// Token_1 mint, the key must grater then token_0 mint.
+#[derive(Accounts)]
+pub struct Graduate<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
     #[account(
+        mut,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump = pool.mint_bump,
+        mint::authority = mint,
         mint::token_program = token_program,
     )]
-    pub token_1_mint: Account<'info, Mint>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"sol-vault", pool.key().as_ref()],
+        bump = pool.sol_vault_bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+    /// CHECK: Raydium cp-swap program
+    #[account(address = raydium_cp_swap::id())]
+    pub cp_swap_program: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump)]
+    pub config: Box<Account<'info, GlobalConfig>>,
+    /// CHECK: Which config the pool belongs to, pinned by the global config.
+    #[account(address = config.amm_config @ Error::InvalidAmmConfig, owner = cp_swap_program.key())]
+    pub amm_config: UncheckedAccount<'info>,
+
+    /// Signs the cp-swap `initialize`, pays for its accounts and receives the LP tokens
+    #[account(
+        mut,
+        seeds = [b"amm-creator", pool.key().as_ref()],
+        bump = pool.amm_creator_bump,
+    )]
+    pub amm_creator: SystemAccount<'info>,
+
+    /// CHECK: pool vault and lp mint authority
+    #[account(
+        seeds = [
+            raydium_cp_swap::AUTH_SEED.as_bytes(),
+        ],
+        seeds::program = cp_swap_program.key(),
+        bump,
+    )]
+    pub authority: UncheckedAccount<'info>,
+
+    /// CHECK: Initialize an account to store the pool state, init by cp-swap
+    #[account(mut)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    #[account(address = native_mint::ID)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// CHECK: pool lp mint, init by cp-swap
-    // #[account(
-    //     mut,
-    //     seeds = [
-    //         POOL_LP_MINT_SEED.as_bytes(),
-    //         pool_state.key().as_ref(),
-    //     ],
-    //     seeds::program = cp_swap_program.key(),
-    //     bump,
-    // )]
-    // pub lp_mint: UncheckedAccount<'info>,
-
-    // /// payer token0 account
-    // #[account(
-    //     init_if_needed,
-    //     token::mint = token_0_mint,
-    //     token::authority = buyer,
-    // )]
-    // pub creator_token_0: Account<'info, TokenAccount>,
-
-    // /// creator token1 account
-    #[account(
-        mut,
-        token::mint = token_1_mint,
-        token::authority = buyer,
-    )]
-    pub creator_token_1: Account<'info, TokenAccount>,
-
-    // /// CHECK: creator lp ATA token account, init by cp-swap
-    // #[account(mut)]
-    // pub creator_lp_token: UncheckedAccount<'info>,
-
-    // /// CHECK: Token_0 vault for the pool, init by cp-swap
-    // #[account(
-    //     mut,
-    //     seeds = [
-    //         POOL_VAULT_SEED.as_bytes(),
-    //         pool_state.key().as_ref(),
-    //         token_0_mint.key().as_ref()
-    //     ],
-    //     seeds::program = cp_swap_program.key(),
-    //     bump,
-    // )]
-    // pub token_0_vault: UncheckedAccount<'info>,
-
-    // /// CHECK: Token_1 vault for the pool, init by cp-swap
-    // #[account(
-    //     mut,
-    //     seeds = [
-    //         POOL_VAULT_SEED.as_bytes(),
-    //         pool_state.key().as_ref(),
-    //         token_1_mint.key().as_ref()
-    //     ],
-    //     seeds::program = cp_swap_program.key(),
-    //     bump,
-    // )]
-    // pub token_1_vault: UncheckedAccount<'info>,
-
-    // /// create pool fee account
-    // #[account(
-    //     mut,
-    //     address= create_pool_fee_reveiver::id(),
-    // )]
-    // pub create_pool_fee: Account<'info, TokenAccount>,
-
-    // /// CHECK: an account to store oracle observations, init by cp-swap
-    // #[account(
-    //     mut,
-    //     seeds = [
-    //         OBSERVATION_SEED.as_bytes(),
-    //         pool_state.key().as_ref(),
-    //     ],
-    //     seeds::program = cp_swap_program.key(),
-    //     bump,
-    // )]
-    // pub observation_state: UncheckedAccount<'info>,
-    // /// Spl token program or token program 2022
-    // pub token_0_program: Program<'info, Token>,
-    // /// Spl token program or token program 2022
-    // pub token_1_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [
+            POOL_LP_MINT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+        ],
+        seeds::program = cp_swap_program.key(),
+        bump,
+    )]
+    pub lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: AMM creator ATA for the pool mint, created on graduation
+    #[account(mut)]
+    pub amm_creator_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: AMM creator ATA for wrapped SOL, created on graduation
+    #[account(mut)]
+    pub amm_creator_wsol_account: UncheckedAccount<'info>,
+
+    /// CHECK: AMM creator lp ATA, init by cp-swap. The program holds the LP tokens so only the
+    /// pool's LP policy decides where they go
+    #[account(
+        mut,
+        address = get_associated_token_address(&amm_creator.key(), &lp_mint.key()),
+    )]
+    pub creator_lp_token: UncheckedAccount<'info>,
+
+    /// CHECK: pool mint vault for the pool, init by cp-swap
+    #[account(
+        mut,
+        seeds = [
+            POOL_VAULT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            mint.key().as_ref()
+        ],
+        seeds::program = cp_swap_program.key(),
+        bump,
+    )]
+    pub token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: wrapped SOL vault for the pool, init by cp-swap
+    #[account(
+        mut,
+        seeds = [
+            POOL_VAULT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            wsol_mint.key().as_ref()
+        ],
+        seeds::program = cp_swap_program.key(),
+        bump,
+    )]
+    pub wsol_vault: UncheckedAccount<'info>,
+
+    /// CHECK: create pool fee account, checked by cp-swap
+    #[account(
+        mut,
+        address = create_pool_fee_reveiver::id(),
+    )]
+    pub create_pool_fee: UncheckedAccount<'info>,
+
+    /// CHECK: an account to store oracle observations, init by cp-swap
+    #[account(
+        mut,
+        seeds = [
+            OBSERVATION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+        ],
+        seeds::program = cp_swap_program.key(),
+        bump,
+    )]
+    pub observation_state: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub wsol_token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
     /// Sysvar for program account
     pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(ticker: String)]
-pub struct Sell<'info> {
-    #[account(mut)]
-    pub seller: Signer<'info>,
+pub struct ClaimProtocolFees<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, GlobalConfig>,
     #[account(
         mut,
-        seeds = [b"pool", ticker.as_bytes()],
+        seeds = [b"treasury"],
         bump,
     )]
+    pub treasury: Account<'info, FeeVault>,
+    /// CHECK: only receives lamports
+    #[account(mut, address = config.protocol_fee_receiver)]
+    pub protocol_fee_receiver: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
     pub pool: Account<'info, Pool>,
     #[account(
         mut,
-        seeds = [b"mint", ticker.as_bytes()],
-        bump,
-        mint::authority = mint,
+        seeds = [b"creator-vault", pool.key().as_ref()],
+        bump = pool.creator_vault_bump,
     )]
-    pub mint: Account<'info, Mint>,
+    pub creator_vault: Account<'info, FeeVault>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpTokens<'info> {
     #[account(mut)]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.index.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        seeds = [b"amm-creator", pool.key().as_ref()],
+        bump = pool.amm_creator_bump,
+    )]
+    pub amm_creator: SystemAccount<'info>,
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, token::Mint>,
+    #[account(
+        mut,
+        associated_token::mint = lp_mint,
+        associated_token::authority = amm_creator,
+    )]
+    pub amm_creator_lp_token: Account<'info, token::TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = lp_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_lp_token: Account<'info, token::TokenAccount>,
+    // cp-swap LP mints are legacy token mints
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(ticker: String)]
 pub struct GetPool<'info> {
-    #[account(seeds = [b"pool", ticker.as_bytes()], bump)]
+    #[account(seeds = [b"pool", pool.index.to_le_bytes().as_ref()], bump = pool.bump)]
     pub pool: Account<'info, Pool>,
 }
 
@@ -646,24 +1912,330 @@ pub struct TokenArgs {
     pub name: String,
     pub symbol: String,
     pub uri: String,
+    pub curve: Option<CurveKind>, // defaults to the quadratic launch curve
+    pub maturity_duration: Option<i64>, // seconds, defaults to the config's maturity duration
+    pub transfer_fee: Option<TransferFeeArgs>, // Token-2022 launches only
+    pub initial_buy: Option<InitialBuyArgs>, // creator's first buy, filled in the launch transaction
+    pub launch: Option<LaunchSettings>,      // anti-sniper limits, none by default
+    pub total_supply: Option<u64>,           // atomic units, defaults to 50k whole tokens
+    pub curve_supply: Option<u64>, // atomic units the curve sells, defaults to 80% of the total
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct InitialBuyArgs {
+    pub amount: u64,       // in atomic token units, under the launch caps like any buy
+    pub max_sol_cost: u64, // lamports, fees included
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct TransferFeeArgs {
+    pub basis_points: u16,
+    pub maximum_fee: u64, // in atomic token units
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Pool {
-    pub ticker: String,
+    pub index: u64, // position in launch order, seeds the pool PDA
+    #[max_len(MAX_TICKER_LEN)]
+    pub ticker: String, // metadata only, tickers don't key any account but the registry
     pub tok_price: u64, // Store price in atomic units (lamports)
     pub mint: Pubkey,
     pub creator: Pubkey,
     pub maturity_time: i64,
-    pub has_matured: bool,
+    pub status: PoolStatus,
+    pub failed_supply: u64,  // mint supply when the pool failed
+    pub failed_balance: u64, // refundable lamports when the pool failed
+    // curve reserves, moved only by trades so rent and stray transfers never count
+    pub real_sol_reserves: u64,   // lamports paid into the curve
+    pub real_token_reserves: u64, // tokens the curve has sold and not bought back
+    pub total_supply: u64,        // most the mint can ever carry, curve and LP reserve together
+    pub curve_supply: u64,        // tokens the curve can sell, the rest is the LP reserve
+    pub curve: CurveKind,
+    pub maturity_balance: u64, // lamports the pool needs to mature, from the config at launch
+    pub protocol_fee_bps: u16,
+    pub creator_fee_bps: u16,
+    pub launch: LaunchSettings,
+    pub lp_policy: LpPolicy,    // from the config at launch
+    pub lp_mint: Pubkey,        // cp-swap LP mint, set on graduation
+    pub lp_vesting_amount: u64, // LP tokens vesting to the creator
+    pub lp_claimed: u64,        // vested LP tokens the creator has claimed
+    pub graduated_at: i64,
+    // canonical bumps, stored so later instructions skip the bump search
+    pub bump: u8,
+    pub escrow_bump: u8,
+    pub mint_bump: u8,
+    pub sol_vault_bump: u8,
+    pub treasury_bump: u8,
+    pub creator_vault_bump: u8,
+    pub amm_creator_bump: u8,
+}
+
+//            ┌──> Matured ──> Graduated
+// Active ────┤       │
+//            └──> Failed ──> Closed
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolStatus {
+    Active,    // trading on the bonding curve
+    Matured,   // reached its maturity balance or sold out, waiting to graduate
+    Graduated, // trading on the AMM
+    Failed,    // missed its milestone, holders can claim refunds
+    Closed,    // every holder of a failed pool has been refunded
+}
+
+// what graduation does with the LP tokens cp-swap mints to the AMM creator
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LpPolicy {
+    Burn, // burned on graduation
+    Lock, // left with the AMM creator PDA, nothing can move them out
+    // released to the creator over `duration` seconds, starting `cliff` seconds after graduation
+    Vest { cliff: i64, duration: i64 },
 }
+
+impl LpPolicy {
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            LpPolicy::Burn | LpPolicy::Lock => true,
+            LpPolicy::Vest { cliff, duration } => {
+                cliff >= 0 && duration > 0 && cliff.saturating_add(duration) <= MAX_LP_VESTING
+            }
+        }
+    }
+}
+
+impl Pool {
+    // the bonding curve is only the venue while the pool is active
+    pub fn require_active(&self) -> Result<()> {
+        match self.status {
+            PoolStatus::Active => Ok(()),
+            PoolStatus::Matured => err!(Error::PoolAlreadyMatured),
+            PoolStatus::Graduated => err!(Error::PoolAlreadyGraduated),
+            PoolStatus::Failed => err!(Error::PoolFailed),
+            PoolStatus::Closed => err!(Error::PoolClosed),
+        }
+    }
+
+    // the curve still holds the pool's SOL until it graduates or fails
+    pub fn require_on_curve(&self) -> Result<()> {
+        match self.status {
+            PoolStatus::Active | PoolStatus::Matured => Ok(()),
+            PoolStatus::Graduated => err!(Error::PoolAlreadyGraduated),
+            PoolStatus::Failed => err!(Error::PoolFailed),
+            PoolStatus::Closed => err!(Error::PoolClosed),
+        }
+    }
+
+    // books a curve buy, true once it completes the curve
+    pub fn record_buy(&mut self, trade: &TradeResult) -> Result<bool> {
+        require!(
+            trade.token_amount <= self.curve_supply_left(),
+            Error::CurveSupplyExhausted
+        );
+        self.real_sol_reserves = self
+            .real_sol_reserves
+            .checked_add(trade.sol_amount)
+            .ok_or(Error::MathOverflow)?;
+        self.real_token_reserves = self
+            .real_token_reserves
+            .checked_add(trade.token_amount)
+            .ok_or(Error::MathOverflow)?;
+        self.tok_price = trade.tok_price;
+
+        let matured = self.is_complete();
+        if matured {
+            self.status = PoolStatus::Matured;
+        }
+        Ok(matured)
+    }
+
+    // the curve completes once it reaches its maturity balance or sells out, lamports sent
+    // straight to the vault don't count
+    pub fn is_complete(&self) -> bool {
+        check_if_maturity_amount_reached(self.real_sol_reserves, self.maturity_balance)
+            || self.real_token_reserves == self.curve_supply
+    }
+
+    // the maturity balance is floored at MIN_MATURITY_BALANCE, a sold out curve may not be
+    pub fn can_graduate(&self) -> bool {
+        self.is_complete() && self.real_sol_reserves >= MIN_MATURITY_BALANCE
+    }
+
+    pub fn curve_supply_left(&self) -> u64 {
+        self.curve_supply.saturating_sub(self.real_token_reserves)
+    }
+
+    // tokens held back from the curve to seed the AMM
+    pub fn lp_reserve_supply(&self) -> u64 {
+        self.total_supply - self.curve_supply
+    }
+
+    pub fn quote_trade(&self, token_amount: u64, sol_amount: u64, tok_price: u64) -> TradeResult {
+        TradeResult {
+            token_amount,
+            sol_amount,
+            protocol_fee: calculate_fee(sol_amount, self.protocol_fee_bps),
+            creator_fee: calculate_fee(sol_amount, self.creator_fee_bps),
+            tok_price,
+        }
+    }
+}
+
+// holds trading fees until they are claimed
+#[account]
+#[derive(InitSpace)]
+pub struct FeeVault {}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct TradeResult {
+    pub token_amount: u64,
+    pub sol_amount: u64, // lamports moved along the curve, before fees
+    pub protocol_fee: u64,
+    pub creator_fee: u64,
+    pub tok_price: u64,
+}
+
+impl TradeResult {
+    pub fn total_fee(&self) -> u64 {
+        self.protocol_fee + self.creator_fee
+    }
+}
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub protocol_fee_receiver: Pubkey, // where protocol fees and forfeited escrows are swept to
+    pub escrow_amount: u64,            // lamports a creator locks up to launch a pool
+    pub maturity_duration: i64,        // default seconds a pool has to reach its maturity balance
+    pub min_maturity_duration: i64,    // shortest maturity duration a creator can pick
+    pub max_maturity_duration: i64,    // longest maturity duration a creator can pick
+    pub maturity_balance: u64,         // lamports a pool needs to mature
+    pub min_tok_price: u64,            // lamports per whole token a curve must start at
+    pub protocol_fee_bps: u16,
+    pub creator_fee_bps: u16,
+    pub forfeit_failed_escrow: bool, // failed pools send the escrow to the treasury
+    pub unique_tickers: bool,        // a ticker can only be launched once
+    pub pool_count: u64,             // pools launched so far, the next pool's index
+    pub lp_policy: LpPolicy,         // what graduation does with the LP tokens of new pools
+    pub amm_config: Pubkey,          // cp-swap fee tier every graduation creates its AMM under
+}
+
+impl GlobalConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.escrow_amount > 0
+                && MIN_MATURITY_DURATION <= self.min_maturity_duration
+                && self.min_maturity_duration <= self.maturity_duration
+                && self.maturity_duration <= self.max_maturity_duration
+                && self.max_maturity_duration <= MAX_MATURITY_DURATION
+                && self.maturity_balance >= MIN_MATURITY_BALANCE
+                && self.min_tok_price >= MIN_TOK_PRICE
+                && self.protocol_fee_bps as u64 + self.creator_fee_bps as u64 <= MAX_TOTAL_FEE_BPS
+                && self.lp_policy.is_valid(),
+            Error::InvalidConfig
+        );
+        Ok(())
+    }
+}
+
+// fields left as `None` keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ConfigArgs {
+    pub admin: Option<Pubkey>,
+    pub protocol_fee_receiver: Option<Pubkey>,
+    pub escrow_amount: Option<u64>,
+    pub maturity_duration: Option<i64>,
+    pub min_maturity_duration: Option<i64>,
+    pub max_maturity_duration: Option<i64>,
+    pub maturity_balance: Option<u64>,
+    pub min_tok_price: Option<u64>,
+    pub protocol_fee_bps: Option<u16>,
+    pub creator_fee_bps: Option<u16>,
+    pub forfeit_failed_escrow: Option<bool>,
+    pub unique_tickers: Option<bool>,
+    pub lp_policy: Option<LpPolicy>,
+    pub amm_config: Option<Pubkey>,
+}
+
+// a wallet's trading history in one pool, backs the per-wallet launch cap
 #[account]
+#[derive(InitSpace)]
+pub struct UserPosition {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub tokens_bought: u64,
+    pub tokens_sold: u64,
+    pub sol_spent: u64,    // lamports paid for buys, fees included
+    pub sol_received: u64, // lamports received from sells, after fees
+    pub first_trade_slot: u64,
+    pub last_trade_slot: u64,
+}
+
+impl UserPosition {
+    pub fn record_buy(
+        &mut self,
+        pool: Pubkey,
+        user: Pubkey,
+        trade: &TradeResult,
+        slot: u64,
+    ) -> Result<()> {
+        self.record_trade(pool, user, slot);
+        self.tokens_bought = self
+            .tokens_bought
+            .checked_add(trade.token_amount)
+            .ok_or(Error::MathOverflow)?;
+        self.sol_spent = self
+            .sol_spent
+            .checked_add(trade.sol_amount + trade.total_fee())
+            .ok_or(Error::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_sell(
+        &mut self,
+        pool: Pubkey,
+        user: Pubkey,
+        trade: &TradeResult,
+        slot: u64,
+    ) -> Result<()> {
+        self.record_trade(pool, user, slot);
+        self.tokens_sold = self
+            .tokens_sold
+            .checked_add(trade.token_amount)
+            .ok_or(Error::MathOverflow)?;
+        self.sol_received = self
+            .sol_received
+            .checked_add(trade.sol_amount - trade.total_fee())
+            .ok_or(Error::MathOverflow)?;
+        Ok(())
+    }
 
+    // a fresh position is stamped with its owner on the first trade
+    fn record_trade(&mut self, pool: Pubkey, user: Pubkey, slot: u64) {
+        if self.user == Pubkey::default() {
+            self.pool = pool;
+            self.user = user;
+            self.first_trade_slot = slot;
+        }
+        self.last_trade_slot = slot;
+    }
+}
+
+// registry entry for a ticker, created on first launch or reservation
+#[account]
+#[derive(InitSpace)]
+pub struct TickerRecord {
+    pub reserved_for: Pubkey, // only this creator can launch the ticker, default when unreserved
+    pub pool: Pubkey,         // latest pool launched with the ticker
+}
+
+#[account]
+#[derive(InitSpace)]
 pub struct PoolEscrow {
     pub pool: Pubkey,
     pub mint: Pubkey,
     pub owner: Pubkey,
     pub balance: u64, // in atomic units (lamports)
+    pub forfeit_on_failure: bool,
 }
 
 #[error_code]
@@ -688,4 +2260,58 @@ pub enum Error {
     PoolInsufficientFunds,
     #[msg("Invalid ticker format")]
     InvalidTickerFormat,
+    #[msg("Pool has already graduated")]
+    PoolAlreadyGraduated,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Invalid bonding curve parameters")]
+    InvalidCurveParameters,
+    #[msg("Amount exceeds what the bonding curve can sell")]
+    CurveSupplyExhausted,
+    #[msg("Trade exceeds slippage tolerance")]
+    SlippageExceeded,
+    #[msg("Not config admin")]
+    NotConfigAdmin,
+    #[msg("Invalid config")]
+    InvalidConfig,
+    #[msg("Pool has matured and can only graduate")]
+    PoolAlreadyMatured,
+    #[msg("Pool failed to mature")]
+    PoolFailed,
+    #[msg("Pool has not failed")]
+    PoolNotFailed,
+    #[msg("Pool has closed")]
+    PoolClosed,
+    #[msg("Pool missed its maturity time")]
+    PoolExpired,
+    #[msg("Maturity duration is outside the config bounds")]
+    InvalidMaturityDuration,
+    #[msg("Transfer fees need a Token-2022 mint")]
+    TransferFeeRequiresToken2022,
+    #[msg("Invalid transfer fee")]
+    InvalidTransferFee,
+    #[msg("Legacy token mints need the Metaplex metadata accounts")]
+    MissingMetadataAccounts,
+    #[msg("Ticker is reserved for another creator")]
+    TickerReserved,
+    #[msg("Ticker has already been launched")]
+    TickerTaken,
+    #[msg("Initial buys need the creator token account and associated token program")]
+    MissingInitialBuyAccounts,
+    #[msg("Invalid launch settings")]
+    InvalidLaunchSettings,
+    #[msg("Trading has not started yet")]
+    TradingNotStarted,
+    #[msg("Buy exceeds the per-transaction cap")]
+    BuyCapExceeded,
+    #[msg("Buy exceeds the per-wallet cap")]
+    WalletCapExceeded,
+    #[msg("Curve supply must be positive and leave the minimum LP reserve")]
+    InvalidSupply,
+    #[msg("Pool LP tokens are not vesting to the creator")]
+    LpTokensNotVesting,
+    #[msg("Only the program upgrade authority can initialize the config")]
+    NotUpgradeAuthority,
+    #[msg("Graduation must use the cp-swap config set in the global config")]
+    InvalidAmmConfig,
 }
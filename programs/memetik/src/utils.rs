@@ -1,39 +1,153 @@
 use crate::constants::*;
-use anchor_lang::solana_program::sysvar::clock::Clock;
-use anchor_lang::solana_program::sysvar::Sysvar;
+use crate::Error;
+use crate::AMM_CONFIG_CREATE_POOL_FEE_OFFSET;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token;
+use anchor_spl::token_2022::{
+    self,
+    spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+            StateWithExtensions,
+        },
+        state::{Account as Token2022Account, Mint as Token2022Mint},
+    },
+};
 
 pub fn check_valid_ticker(s: &str) -> bool {
-    if s.is_empty() {
+    if s.is_empty() || s.len() > MAX_TICKER_LEN {
         return false;
     }
     s.chars().all(|c| c.is_ascii_uppercase())
 }
 
-pub fn to_whole_units(amount: u64) -> f64 {
-    amount as f64 / TOKEN_SCALE
+pub fn calculate_maturity_time(maturity_duration: i64) -> i64 {
+    let current_timestamp = Clock::get().unwrap().unix_timestamp;
+    let maturity_date = current_timestamp + maturity_duration;
+    maturity_date
 }
 
-pub fn to_atomic_units(amount: f64) -> u64 {
-    (amount * TOKEN_SCALE).round() as u64
+pub fn check_if_maturity_time_passed(maturity_date: i64) -> bool {
+    let current_timestamp = Clock::get().unwrap().unix_timestamp;
+    current_timestamp >= maturity_date
 }
 
-pub fn calculate_maturity_time() -> i64 {
-    let current_timestamp = Clock::get().unwrap().unix_timestamp;
-    let maturity_date = current_timestamp + DAYS_TO_MATURITY * SECONDS_IN_A_DAY;
-    maturity_date
+pub fn check_if_maturity_amount_reached(pool_balance_lamports: u64, maturity_balance: u64) -> bool {
+    pool_balance_lamports >= maturity_balance
 }
 
-pub fn calculate_test_time() -> i64 {
-    let current_timestamp = Clock::get().unwrap().unix_timestamp;
-    let test_date = current_timestamp + 3; // x seconds from now
-    test_date
+// amount of tokens (atomic units) worth `sol_amount` lamports at the given price per whole unit
+pub fn calculate_liquidity_token_amount(sol_amount: u64, price_per_unit: u64) -> u64 {
+    (sol_amount as u128 * TOKEN_SCALE as u128 / price_per_unit.max(1) as u128) as u64
 }
 
-pub fn check_if_maturity_time_passed(maturity_date: i64) -> bool {
-    let current_timestamp = Clock::get().unwrap().unix_timestamp;
-    current_timestamp >= maturity_date
+// share of `total` released linearly over `duration` seconds from `start`, nothing before it
+pub fn calculate_vested_amount(total: u64, start: i64, duration: i64, now: i64) -> u64 {
+    if now <= start {
+        return 0;
+    }
+    let elapsed = now - start;
+    if elapsed >= duration {
+        return total;
+    }
+    (total as u128 * elapsed as u128 / duration as u128) as u64
+}
+
+// cp-swap is built on an older anchor, so its `AmmConfig` is read from the raw account data
+pub fn read_amm_config_create_pool_fee(data: &[u8]) -> Option<u64> {
+    let bytes =
+        data.get(AMM_CONFIG_CREATE_POOL_FEE_OFFSET..AMM_CONFIG_CREATE_POOL_FEE_OFFSET + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+// fee on a trade of `sol_amount` lamports, rounded down
+pub fn calculate_fee(sol_amount: u64, fee_bps: u16) -> u64 {
+    (sol_amount as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
 }
 
-pub fn check_if_maturity_amount_reached(pool_balance_lamports: u64) -> bool {
-    pool_balance_lamports >= REQUIRED_POOL_BALANCE_TO_MATURE
+// largest amount that, with `fee_bps` on top, still fits in `sol_amount`
+pub fn calculate_amount_before_fees(sol_amount: u64, fee_bps: u16) -> u64 {
+    (sol_amount as u128 * BPS_DENOMINATOR as u128 / (BPS_DENOMINATOR + fee_bps as u64) as u128)
+        as u64
+}
+
+// lamports a fee account holds above its rent exempt minimum
+pub fn claimable_fees(fee_account: &AccountInfo) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(fee_account.data_len());
+    Ok(fee_account.lamports().saturating_sub(rent))
+}
+
+// share of `failed_balance` owed to `amount` out of `failed_supply` tokens, rounded down so the
+// claims never add up to more than the pool holds
+pub fn calculate_refund(amount: u64, failed_supply: u64, failed_balance: u64) -> u64 {
+    if failed_supply == 0 {
+        return 0;
+    }
+    (amount as u128 * failed_balance as u128 / failed_supply as u128) as u64
+}
+
+// pays `lamports` out of a pool's system-owned sol vault, which signs with its own seeds
+pub fn transfer_from_sol_vault<'info>(
+    sol_vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    pool: &Pubkey,
+    sol_vault_bump: u8,
+    lamports: u64,
+) -> Result<()> {
+    if lamports == 0 {
+        return Ok(());
+    }
+    let seeds = &["sol-vault".as_bytes(), pool.as_ref(), &[sol_vault_bump]];
+    invoke_signed(
+        &system_instruction::transfer(sol_vault.key, to.key, lamports),
+        &[sol_vault.clone(), to.clone(), system_program.clone()],
+        &[&seeds[..]],
+    )?;
+    Ok(())
+}
+
+// size of a token account for `mint`, Token-2022 accounts carry the extensions their mint requires
+pub fn token_account_len(mint: &AccountInfo) -> Result<usize> {
+    if *mint.owner != token_2022::ID {
+        return Ok(token::TokenAccount::LEN);
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+    let mut extensions =
+        ExtensionType::get_required_init_account_extensions(&mint_state.get_extension_types()?);
+    // associated token accounts are always created immutable
+    extensions.push(ExtensionType::ImmutableOwner);
+    Ok(ExtensionType::try_calculate_account_len::<Token2022Account>(&extensions)?)
+}
+
+// transfer fees harvested into the mint, they count toward its supply but sit in no holder's account
+pub fn withheld_transfer_fees(mint: &AccountInfo) -> Result<u64> {
+    if *mint.owner != token_2022::ID {
+        return Ok(0);
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    Ok(u64::from(transfer_fee_config.withheld_amount))
+}
+
+// amount to send so `amount` arrives after the mint's Token-2022 transfer fee, if it has one
+pub fn amount_before_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != token_2022::ID {
+        return Ok(amount);
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let fee = transfer_fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(Error::MathOverflow)?;
+    Ok(amount.checked_add(fee).ok_or(Error::MathOverflow)?)
 }
@@ -3,7 +3,18 @@ import { Memetik } from '../target/types/memetik';
 import { assert } from 'chai';
 import { getLogs } from '@solana-developers/helpers';
 import { LAMPORTS_PER_SOL } from '@solana/web3.js';
-import { createMint } from '@solana/spl-token';
+import {
+  createAssociatedTokenAccount,
+  createMint,
+  getAssociatedTokenAddressSync,
+  getMint,
+  getTokenMetadata,
+  getTransferFeeConfig,
+  harvestWithheldTokensToMint,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  transferChecked,
+} from '@solana/spl-token';
 import { createMintWithTransferFee } from './utils';
 
 type PoolFromProgram = {
@@ -25,6 +36,10 @@ const getSol = (lamports: number) => {
   return lamports / LAMPORTS_PER_SOL;
 };
 
+const createPoolFeeReceiver = new anchor.web3.PublicKey(
+  'G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2'
+);
+
 const TOKEN_METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
   'metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s'
 );
@@ -54,6 +69,45 @@ export async function getAmmConfigAddress(
   return [address, bump];
 }
 
+export function getCpSwapPoolAddress(
+  ammConfig: anchor.web3.PublicKey,
+  mintA: anchor.web3.PublicKey,
+  mintB: anchor.web3.PublicKey
+) {
+  // cp-swap orders the pool mints by key
+  const [token0, token1] =
+    Buffer.compare(mintA.toBuffer(), mintB.toBuffer()) < 0
+      ? [mintA, mintB]
+      : [mintB, mintA];
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('pool'),
+      ammConfig.toBuffer(),
+      token0.toBuffer(),
+      token1.toBuffer(),
+    ],
+    raydiumSwapProgramId
+  );
+  return address;
+}
+export function getCpSwapLpMintAddress(poolState: anchor.web3.PublicKey) {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('pool_lp_mint'), poolState.toBuffer()],
+    raydiumSwapProgramId
+  );
+  return address;
+}
+export function getCpSwapVaultAddress(
+  poolState: anchor.web3.PublicKey,
+  mint: anchor.web3.PublicKey
+) {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('pool_vault'), poolState.toBuffer(), mint.toBuffer()],
+    raydiumSwapProgramId
+  );
+  return address;
+}
+
 // list of tokens to be created
 const tokens = [
   {
@@ -66,6 +120,11 @@ const tokens = [
     symbol: 'WOO',
     uri: '',
   },
+  {
+    name: 'graduate',
+    symbol: 'GRAD',
+    uri: '',
+  },
 ];
 
 // Configure the client to use the local cluster.
@@ -130,6 +189,15 @@ const waitForTxnConfrimation = async (
   return confirmedTxn;
 };
 
+const getComputeUnits = async (tx: anchor.web3.TransactionSignature) => {
+  for (let attempt = 0; attempt < 20; attempt++) {
+    const confirmedTxn = await waitForTxnConfrimation(tx);
+    if (confirmedTxn) return confirmedTxn.meta.computeUnitsConsumed;
+    await sleep(500);
+  }
+  throw new Error(`Transaction ${tx} was not confirmed`);
+};
+
 const fundSol = async (
   receiver: anchor.web3.PublicKey,
   solAmt = 80000
@@ -163,7 +231,10 @@ const mintFake = async () => {
 
 const getMintPDA = (ticker: string) => {
   const MINT_SEED_CONSTANT = 'mint';
-  const seeds = [Buffer.from(MINT_SEED_CONSTANT), Buffer.from(ticker)];
+  const seeds = [
+    Buffer.from(MINT_SEED_CONSTANT),
+    getPoolPDA(ticker).toBuffer(),
+  ];
   const [mintPDA] = anchor.web3.PublicKey.findProgramAddressSync(
     seeds,
     program.programId
@@ -182,18 +253,116 @@ const getMetadataPDA = (mint: anchor.web3.PublicKey) => {
   );
   return metadataAddress;
 };
-const getPoolPDA = (ticker: string) => {
+// pools are keyed by launch index, the tests look them up by ticker
+const poolsByTicker: { [ticker: string]: anchor.web3.PublicKey } = {};
+const getPoolPDAByIndex = (index: anchor.BN) => {
   const POOL_SEED_CONSTANT = 'pool';
-  const seeds = [Buffer.from(POOL_SEED_CONSTANT), Buffer.from(ticker)];
+  const seeds = [
+    Buffer.from(POOL_SEED_CONSTANT),
+    index.toArrayLike(Buffer, 'le', 8),
+  ];
   const [poolPDA] = anchor.web3.PublicKey.findProgramAddressSync(
     seeds,
     program.programId
   );
   return poolPDA;
 };
+const getPoolPDA = (ticker: string) => poolsByTicker[ticker];
+// points `ticker` at the pool the next launch creates
+const assignNextPoolPDA = async (ticker: string) => {
+  const config = await program.account.globalConfig.fetch(getConfigPDA());
+  poolsByTicker[ticker] = getPoolPDAByIndex(config.poolCount);
+  return poolsByTicker[ticker];
+};
+const getPositionPDA = (
+  ticker: string,
+  user: anchor.web3.PublicKey
+) => {
+  const [positionPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('position'),
+      getPoolPDA(ticker).toBuffer(),
+      user.toBuffer(),
+    ],
+    program.programId
+  );
+  return positionPDA;
+};
+const getTickerRecordPDA = (ticker: string) => {
+  const [tickerRecordPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('ticker'), Buffer.from(ticker)],
+    program.programId
+  );
+  return tickerRecordPDA;
+};
+const getSolVaultPDA = (ticker: string) => {
+  const [solVaultPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('sol-vault'), getPoolPDA(ticker).toBuffer()],
+    program.programId
+  );
+  return solVaultPDA;
+};
+// launches pick the token program, so look it up from the mint's owner
+const getTokenProgram = async (ticker: string) => {
+  const mintInfo = await provider.connection.getAccountInfo(
+    getMintPDA(ticker)
+  );
+  return mintInfo.owner;
+};
+// `anchor test` deploys memetik upgradeable, owned by the provider wallet
+const getProgramDataAddress = () => {
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+  return programData;
+};
+const getConfigPDA = () => {
+  const [configPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('config')],
+    program.programId
+  );
+  return configPDA;
+};
+
+// `updateConfig` leaves fields set to null untouched
+const emptyConfigArgs = {
+  admin: null,
+  protocolFeeReceiver: null,
+  escrowAmount: null,
+  maturityDuration: null,
+  minMaturityDuration: null,
+  maxMaturityDuration: null,
+  maturityBalance: null,
+  minTokPrice: null,
+  protocolFeeBps: null,
+  creatorFeeBps: null,
+  forfeitFailedEscrow: null,
+  uniqueTickers: null,
+  lpPolicy: null,
+  ammConfig: null,
+};
+// every launch limit off
+const emptyLaunchSettings = {
+  tradingStartTime: new anchor.BN(0),
+  maxWalletTokens: new anchor.BN(0),
+  walletCapDuration: new anchor.BN(0),
+  walletCapSupply: new anchor.BN(0),
+  maxBuyTokens: new anchor.BN(0),
+  maxBuyGrowth: new anchor.BN(0),
+};
+// long enough for every trade against the first pools to land before they
+// expire
+const TEST_MATURITY_SECONDS = 90;
+// GRADUATION_GRACE_PERIOD under the test-maturity feature
+const GRADUATION_GRACE_SECONDS = 30;
+
 const getEscrowPDA = (ticker: string) => {
   const ESCROW_SEED_CONSTANT = 'pool-escrow';
-  const seeds = [Buffer.from(ESCROW_SEED_CONSTANT), Buffer.from(ticker)];
+  const seeds = [
+    Buffer.from(ESCROW_SEED_CONSTANT),
+    getPoolPDA(ticker).toBuffer(),
+  ];
   const [escrowPDA] = anchor.web3.PublicKey.findProgramAddressSync(
     seeds,
     program.programId
@@ -201,52 +370,135 @@ const getEscrowPDA = (ticker: string) => {
   return escrowPDA;
 };
 
-const buyTokens = async (buyer: any, ticker: string, amount: number) => {
+const getAmmCreatorPDA = (ticker: string) => {
+  const AMM_CREATOR_SEED_CONSTANT = 'amm-creator';
+  const seeds = [
+    Buffer.from(AMM_CREATOR_SEED_CONSTANT),
+    getPoolPDA(ticker).toBuffer(),
+  ];
+  const [ammCreatorPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    seeds,
+    program.programId
+  );
+  return ammCreatorPDA;
+};
+
+// accounts cp-swap needs to graduate a pool
+const getGraduationAccounts = async (ticker: string) => {
+  const mint = getMintPDA(ticker);
+  const ammCreator = getAmmCreatorPDA(ticker);
+  const [ammConfig] = await getAmmConfigAddress(0, raydiumSwapProgramId);
+  const poolState = getCpSwapPoolAddress(ammConfig, mint, SOL_MINT);
+  const lpMint = getCpSwapLpMintAddress(poolState);
+  return {
+    cpSwapProgram: raydiumSwapProgramId,
+    ammConfig,
+    poolState,
+    createPoolFee: createPoolFeeReceiver,
+    ammCreatorTokenAccount: getAssociatedTokenAddressSync(
+      mint,
+      ammCreator,
+      true
+    ),
+    ammCreatorWsolAccount: getAssociatedTokenAddressSync(
+      SOL_MINT,
+      ammCreator,
+      true
+    ),
+    creatorLpToken: getAssociatedTokenAddressSync(
+      lpMint,
+      ammCreator,
+      true
+    ),
+    pool: getPoolPDA(ticker),
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+};
+
+const U64_MAX = new anchor.BN('18446744073709551615');
+
+// launches a legacy token pool and points its ticker at it
+const launchToken = async (creator: any, token: any) => {
+  await assignNextPoolPDA(token.symbol);
+  const txn = await program.methods
+    .initialize(token)
+    .accounts({
+      signer: creator.publicKey,
+      metadata: getMetadataPDA(getMintPDA(token.symbol)),
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([creator])
+    .rpc();
+  return txn;
+};
+
+const buyTokens = async (
+  buyer: any,
+  ticker: string,
+  amount: number,
+  maxSolCost = U64_MAX
+) => {
   const poolPDA = getPoolPDA(ticker);
   const poolFromProgram = await program.account.pool.fetch(poolPDA);
-  const buyerTokenAccount = await anchor.utils.token.associatedAddress({
-    mint: getMintPDA(ticker),
-    owner: buyer.publicKey,
-  });
+  const tokenProgram = await getTokenProgram(ticker);
 
   const priceInSol = getSol(poolFromProgram.tokPrice.toNumber());
   console.log(
     `Buyer buying ${amount} tokens for ${priceInSol} SOL per token`
   );
-  const [ammConfigAddress, _] = await getAmmConfigAddress(
-    0,
-    raydiumSwapProgramId
-  );
   const txn = await program.methods
-    .buy(ticker, new anchor.BN(amount))
+    .buy(new anchor.BN(amount), maxSolCost)
     .accounts({
       buyer: buyer.publicKey,
-      cpSwapProgram: raydiumSwapProgramId,
-      ammConfig: ammConfigAddress,
-      token1Mint: SOL_MINT,
-      creatorToken1: buyer.publicKey,
+      pool: poolPDA,
+      tokenProgram,
     })
     .signers([buyer])
     .rpc();
   return txn;
 };
 
-const sellTokens = async (seller: any, ticker: string, amount: number) => {
+// permissionless, cranked by the provider wallet
+const graduatePool = async (ticker: string) => {
+  const graduationAccounts = await getGraduationAccounts(ticker);
+  const txn = await program.methods
+    .graduate()
+    .accountsPartial(graduationAccounts)
+    .preInstructions([
+      anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({
+        units: 1_400_000,
+      }),
+    ])
+    .rpc();
+  return txn;
+};
+
+const sellTokens = async (
+  seller: any,
+  ticker: string,
+  amount: number,
+  minSolOut = new anchor.BN(0)
+) => {
   const poolPDA = getPoolPDA(ticker);
   const poolFromPogram = await program.account.pool.fetch(poolPDA);
-  const sellerTokenAccount = await anchor.utils.token.associatedAddress({
-    mint: getMintPDA(ticker),
-    owner: seller.publicKey,
-  });
+  const tokenProgram = await getTokenProgram(ticker);
+  const sellerTokenAccount = getAssociatedTokenAddressSync(
+    getMintPDA(ticker),
+    seller.publicKey,
+    false,
+    tokenProgram
+  );
   const priceInSol = getSol(poolFromPogram.tokPrice.toNumber());
   console.log(
     `Seller selling ${amount} tokens at ${priceInSol} SOL per token`
   );
   const txn = await program.methods
-    .sell(ticker, new anchor.BN(amount))
+    .sell(new anchor.BN(amount), minSolOut)
     .accounts({
       seller: seller.publicKey,
+      pool: poolPDA,
       sellerTokenAccount,
+      tokenProgram,
     })
     .signers([seller])
     .rpc();
@@ -268,10 +520,96 @@ describe('memetik', () => {
     await mintFake();
   });
 
+  it('bad actor can NOT initialize global config', async () => {
+    const badActor = userB;
+    try {
+      await program.methods
+        .initializeConfig()
+        .accounts({
+          admin: badActor.publicKey,
+          programData: getProgramDataAddress(),
+        })
+        .signers([badActor])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'NotUpgradeAuthority');
+    }
+  });
+
+  it('Can initialize global config', async () => {
+    try {
+      await program.methods
+        .initializeConfig()
+        .accounts({
+          admin: provider.publicKey,
+          programData: getProgramDataAddress(),
+        })
+        .rpc();
+      const config = await program.account.globalConfig.fetch(
+        getConfigPDA()
+      );
+      assert.ok(config.admin.equals(provider.publicKey));
+      assert.ok(config.protocolFeeReceiver.equals(provider.publicKey));
+      const [ammConfig] = await getAmmConfigAddress(
+        0,
+        raydiumSwapProgramId
+      );
+      assert.ok(config.ammConfig.equals(ammConfig));
+    } catch (err) {
+      console.log('Initialize config err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Admin can update global config', async () => {
+    try {
+      // keep pools maturing within the test run
+      await program.methods
+        .updateConfig({
+          ...emptyConfigArgs,
+          maturityDuration: new anchor.BN(TEST_MATURITY_SECONDS),
+        })
+        .accounts({
+          admin: provider.publicKey,
+        })
+        .rpc();
+      const config = await program.account.globalConfig.fetch(
+        getConfigPDA()
+      );
+      assert.ok(
+        config.maturityDuration.toNumber() === TEST_MATURITY_SECONDS
+      );
+    } catch (err) {
+      console.log('Update config err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('bad actor can NOT update global config', async () => {
+    const badActor = userB;
+    try {
+      await program.methods
+        .updateConfig({
+          ...emptyConfigArgs,
+          admin: badActor.publicKey,
+        })
+        .accounts({
+          admin: badActor.publicKey,
+        })
+        .signers([badActor])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'NotConfigAdmin');
+    }
+  });
+
   it('Can launch token', async () => {
     const token = tokens[0];
     try {
       const creator = userA;
+      await assignNextPoolPDA(token.symbol);
       const mint = getMintPDA(token.symbol);
       const metadata = getMetadataPDA(mint);
       const txn = await program.methods
@@ -279,6 +617,7 @@ describe('memetik', () => {
         .accounts({
           signer: creator.publicKey,
           metadata,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -295,8 +634,13 @@ describe('memetik', () => {
           owner: creator.publicKey,
         });
       const creatorTokenBalance = await getSPLBalance(creatorTokenAccount);
+      const config = await program.account.globalConfig.fetch(
+        getConfigPDA()
+      );
       assert.ok(pool);
       assert.ok(creatorTokenBalance === 0);
+      assert.ok(pool.maturityBalance.eq(config.maturityBalance));
+      assert.ok(escrowAcc.balance.eq(config.escrowAmount));
       assert.ok(escrowSolBalance > 0);
       assert.ok(escrowSolBalance >= escrowAcc.balance.toNumber());
       createdPools.push(pool);
@@ -310,7 +654,8 @@ describe('memetik', () => {
   it('Can fetch pool', async () => {
     const pool = createdPools[0];
     const poolFromProgram = await program.methods
-      .getPool(pool.ticker)
+      .getPool()
+      .accounts({ pool: getPoolPDA(pool.ticker) })
       .view();
     assert.ok(poolFromProgram.ticker === pool.ticker);
   });
@@ -318,6 +663,7 @@ describe('memetik', () => {
   it('Can launch another token', async () => {
     const token = tokens[1];
     const creator = userA;
+    await assignNextPoolPDA(token.symbol);
     const mint = getMintPDA(token.symbol);
     const metadata = getMetadataPDA(mint);
     try {
@@ -326,6 +672,7 @@ describe('memetik', () => {
         .accounts({
           signer: creator.publicKey,
           metadata,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -349,121 +696,759 @@ describe('memetik', () => {
     }
   });
 
-  it('can NOT close pool before maturity', async () => {
+  it('Can launch token on a linear curve', async () => {
+    const token = {
+      name: 'linear',
+      symbol: 'LINE',
+      uri: '',
+      maturityDuration: new anchor.BN(600),
+      curve: {
+        linear: {
+          0: {
+            // 1 lamport per whole token, per whole token sold
+            slope: new anchor.BN(1_000_000_000_000),
+            basePrice: new anchor.BN(10),
+          },
+        },
+      },
+    };
     const creator = userA;
-    const pool = createdPools[0];
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
     try {
       await program.methods
-        .close(pool.ticker)
+        .initialize(token)
         .accounts({
-          creator: creator.publicKey,
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
-      assert.fail('Transaction failed');
+      const pool = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      const secondsToMaturity =
+        pool.maturityTime.toNumber() - Math.floor(Date.now() / 1000);
+      assert.ok(pool.curve.linear);
+      assert.ok(pool.tokPrice.toNumber() === 10);
+      // the creator picked a 10 minute window
+      assert.ok(secondsToMaturity > 540 && secondsToMaturity <= 600);
     } catch (err) {
-      assert.ok(err?.error?.errorCode?.code === 'PoolNotMatured');
+      console.log('Create linear token', err);
+      assert.fail('Transaction failed');
     }
   });
 
-  it('Can buy tokens', async () => {
-    const buyer = userB;
-    const amount = 1;
-    const pool = createdPools[0];
+  it('can NOT launch token with a maturity duration out of bounds', async () => {
+    const token = {
+      name: 'forever',
+      symbol: 'LONG',
+      uri: '',
+      maturityDuration: new anchor.BN(365 * 24 * 60 * 60),
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
     try {
-      const buyerTokenAccount = await anchor.utils.token.associatedAddress(
-        {
-          mint: getMintPDA(pool.ticker),
-          owner: buyer.publicKey,
-        }
-      );
-      const solBalBefore = await getSOLBalance(buyer.publicKey);
-      const tokBalBefore = await getSPLBalance(buyerTokenAccount);
-      await buyTokens(buyer, pool.ticker, amount);
-      const solBalAfter = await getSOLBalance(buyer.publicKey);
-      const tokBalAfter = await getSPLBalance(buyerTokenAccount);
-      assert.ok(solBalAfter < solBalBefore);
-      assert.ok(tokBalAfter > tokBalBefore);
-    } catch (err) {
-      console.log('Can buy err', err);
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
       assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(
+        err?.error?.errorCode?.code === 'InvalidMaturityDuration'
+      );
     }
   });
-  it('Token price increases with demand', async () => {
-    const buyer = userC;
-    const pool = createdPools[0];
-    const poolPDA = getPoolPDA(pool.ticker);
+
+  it('can NOT launch token with a ticker that is too long', async () => {
+    const token = {
+      name: 'long ticker',
+      symbol: 'ELEVENCHARS',
+      uri: '',
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
     try {
-      const buyerTokenAccount = await anchor.utils.token.associatedAddress(
-        {
-          mint: getMintPDA(pool.ticker),
-          owner: buyer.publicKey,
-        }
-      );
-      const totalAmountToBy = getLamports(250);
-      const batchAmount = totalAmountToBy / 12;
-      let amountPurchased = 0;
-      while (amountPurchased < totalAmountToBy) {
-        const pool = await program.account.pool.fetch(poolPDA);
-        let tokPriceBefore = pool.tokPrice;
-        let solBalBefore = await getSOLBalance(buyer.publicKey);
-        let tokBalBefore = await getSPLBalance(buyerTokenAccount);
-        await buyTokens(buyer, pool.ticker, batchAmount);
-        const poolAfter = await program.account.pool.fetch(poolPDA);
-        const solBalanceAfter = await getSOLBalance(buyer.publicKey);
-        const tokenBalanceAfter = await getSPLBalance(buyerTokenAccount);
-        console.log('solBalBefore', solBalBefore);
-        console.log('solBalanceAfter', solBalanceAfter);
-        console.log('tokBalBefore', tokBalBefore);
-        console.log('tokenBalanceAfter', tokenBalanceAfter);
-        console.log('New Token price:', poolAfter.tokPrice.toNumber());
-        assert.ok(solBalanceAfter < solBalBefore);
-        assert.ok(tokenBalanceAfter > tokBalBefore);
-        assert.ok(
-          poolAfter.tokPrice.toNumber() >= tokPriceBefore.toNumber()
-        );
-        tokPriceBefore = poolAfter.tokPrice;
-        solBalBefore = solBalanceAfter;
-        amountPurchased += batchAmount;
-      }
-    } catch (err) {
-      console.log('Can buy demand error', err);
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
       assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'InvalidTickerFormat');
     }
   });
-  it('Can sell tokens', async () => {
-    const seller = userB;
-    const pool = createdPools[0];
-    const poolPDA = getPoolPDA(pool.ticker);
+
+  it('can NOT launch token with invalid curve parameters', async () => {
+    const token = {
+      name: 'broken',
+      symbol: 'BRKN',
+      uri: '',
+      curve: {
+        constantProduct: {
+          0: {
+            virtualSolReserves: new anchor.BN(0),
+            virtualTokenReserves: new anchor.BN(0),
+          },
+        },
+      },
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
     try {
-      const poolBefore = await program.account.pool.fetch(poolPDA);
-      const sellerTokenAccount =
-        await anchor.utils.token.associatedAddress({
-          mint: getMintPDA(pool.ticker),
-          owner: seller.publicKey,
-        });
-      const sellerSolBalBefore = await getSOLBalance(seller.publicKey);
-      const sellerTokBalBefore = await getSPLBalance(sellerTokenAccount);
-      const priceBefore = poolBefore.tokPrice;
-      await sellTokens(seller, pool.ticker, sellerTokBalBefore);
-      const sellerSolBalAfter = await getSOLBalance(seller.publicKey);
-      const sellerTokBalAfter = await getSPLBalance(sellerTokenAccount);
-      const poolAfter = await program.account.pool.fetch(poolPDA);
-      assert.ok(sellerSolBalAfter >= sellerSolBalBefore);
-      assert.ok(sellerTokBalAfter < sellerTokBalBefore);
-      assert.ok(poolAfter.tokPrice.toNumber() <= priceBefore.toNumber());
-    } catch (err) {
-      console.log('Can sell err', err);
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
       assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(
+        err?.error?.errorCode?.code === 'InvalidCurveParameters'
+      );
     }
   });
-  it('Token price decresses when supply decreases', async () => {
-    const seller = userC;
-    const pool = createdPools[0];
-    const poolPDA = getPoolPDA(pool.ticker);
+
+  it('Can launch token on Token-2022 with a transfer fee', async () => {
+    const token = {
+      name: 'token two',
+      symbol: 'TWOK',
+      uri: '',
+      transferFee: {
+        basisPoints: 100,
+        maximumFee: new anchor.BN(getLamports(1_000)),
+      },
+    };
+    const creator = userB;
+    const trader = userC;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
     try {
-      const sellerTokenAccount =
-        await anchor.utils.token.associatedAddress({
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: null,
+          tokenMetadataProgram: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      const mintInfo = await getMint(
+        provider.connection,
+        mint,
+        'confirmed',
+        TOKEN_2022_PROGRAM_ID
+      );
+      const metadata = await getTokenMetadata(provider.connection, mint);
+      const transferFee = getTransferFeeConfig(mintInfo).newerTransferFee;
+      assert.ok(metadata.symbol === token.symbol);
+      assert.ok(metadata.updateAuthority.equals(mint));
+      assert.ok(transferFee.transferFeeBasisPoints === 100);
+
+      // the curve mints and burns, so trading it never pays the transfer fee
+      const amount = getLamports(1_000);
+      await buyTokens(trader, token.symbol, amount);
+      const traderTokenAccount = getAssociatedTokenAddressSync(
+        mint,
+        trader.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+      assert.ok((await getSPLBalance(traderTokenAccount)) === amount);
+      await sellTokens(trader, token.symbol, amount);
+      const supply = await provider.connection.getTokenSupply(mint);
+      assert.ok(supply.value.amount === '0');
+    } catch (err) {
+      console.log('Create Token-2022 token', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT add a transfer fee to a legacy token mint', async () => {
+    const token = {
+      name: 'legacy fee',
+      symbol: 'LFEE',
+      uri: '',
+      transferFee: {
+        basisPoints: 100,
+        maximumFee: new anchor.BN(getLamports(1_000)),
+      },
+    };
+    const creator = userB;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(
+        err?.error?.errorCode?.code === 'TransferFeeRequiresToken2022'
+      );
+    }
+  });
+
+  it('Creator can buy in the launch transaction', async () => {
+    const amount = getLamports(1_000);
+    const token = {
+      name: 'first buy',
+      symbol: 'FRST',
+      uri: '',
+      initialBuy: { amount: new anchor.BN(amount), maxSolCost: U64_MAX },
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    const creatorTokenAccount = getAssociatedTokenAddressSync(
+      mint,
+      creator.publicKey
+    );
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          creatorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      const pool = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      assert.ok((await getSPLBalance(creatorTokenAccount)) === amount);
+      assert.ok(pool.realTokenReserves.toNumber() === amount);
+      assert.ok(
+        pool.realSolReserves.toNumber() ===
+          (await getSOLBalance(getSolVaultPDA(token.symbol))) -
+            (await provider.connection.getMinimumBalanceForRentExemption(0))
+      );
+    } catch (err) {
+      console.log('Launch with initial buy err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT launch with an initial buy past its max SOL cost', async () => {
+    const token = {
+      name: 'first buy',
+      symbol: 'FRST',
+      uri: '',
+      initialBuy: {
+        amount: new anchor.BN(getLamports(1_000)),
+        maxSolCost: new anchor.BN(1),
+      },
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          creatorTokenAccount: getAssociatedTokenAddressSync(
+            mint,
+            creator.publicKey
+          ),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'SlippageExceeded');
+    }
+  });
+
+  it('can NOT launch with an initial buy past the launch caps', async () => {
+    const token = {
+      name: 'first buy',
+      symbol: 'FRST',
+      uri: '',
+      initialBuy: {
+        amount: new anchor.BN(getLamports(601)),
+        maxSolCost: U64_MAX,
+      },
+      launch: {
+        ...emptyLaunchSettings,
+        maxBuyTokens: new anchor.BN(getLamports(600)),
+      },
+    };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          creatorTokenAccount: getAssociatedTokenAddressSync(
+            mint,
+            creator.publicKey
+          ),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'BuyCapExceeded');
+    }
+  });
+
+  it('can NOT buy before the trading start time', async () => {
+    const now = Math.floor(Date.now() / 1000);
+    const token = {
+      name: 'gated',
+      symbol: 'GATE',
+      uri: '',
+      maturityDuration: new anchor.BN(2 * 3600),
+      launch: {
+        ...emptyLaunchSettings,
+        tradingStartTime: new anchor.BN(now + 3600),
+      },
+    };
+    await launchToken(userA, token);
+    try {
+      await buyTokens(userB, token.symbol, getLamports(1));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'TradingNotStarted');
+    }
+  });
+
+  it('Launch caps limit buys per transaction and per wallet', async () => {
+    const token = {
+      name: 'capped',
+      symbol: 'CAPD',
+      uri: '',
+      launch: {
+        ...emptyLaunchSettings,
+        maxWalletTokens: new anchor.BN(getLamports(1_000)),
+        walletCapDuration: new anchor.BN(3600),
+        maxBuyTokens: new anchor.BN(getLamports(600)),
+      },
+    };
+    const buyer = userB;
+    await launchToken(userA, token);
+    try {
+      await buyTokens(buyer, token.symbol, getLamports(601));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'BuyCapExceeded');
+    }
+    await buyTokens(buyer, token.symbol, getLamports(600));
+    try {
+      await buyTokens(buyer, token.symbol, getLamports(401));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'WalletCapExceeded');
+    }
+    await buyTokens(buyer, token.symbol, getLamports(400));
+  });
+
+  it('Curve completes once its supply sells out', async () => {
+    const curveSupply = getLamports(800);
+    const token = {
+      name: 'capped supply',
+      symbol: 'FULL',
+      uri: '',
+      totalSupply: new anchor.BN(getLamports(1_000)),
+      curveSupply: new anchor.BN(curveSupply),
+    };
+    const buyer = userB;
+    await launchToken(userA, token);
+    try {
+      await buyTokens(buyer, token.symbol, curveSupply + 1);
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'CurveSupplyExhausted');
+    }
+    await buyTokens(buyer, token.symbol, curveSupply);
+    const pool = await program.account.pool.fetch(getPoolPDA(token.symbol));
+    assert.ok(pool.realTokenReserves.toNumber() === curveSupply);
+    assert.ok('matured' in pool.status);
+    // 800 tokens sell for a fraction of what the AMM accounts cost
+    try {
+      await graduatePool(token.symbol);
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolInsufficientFunds');
+    }
+  });
+
+  it('can NOT launch a curve without the minimum LP reserve', async () => {
+    const creator = userA;
+    // the whole supply, then 95% of it, on the curve
+    for (const curveSupply of [1_000, 950]) {
+      const token = {
+        name: 'no reserve',
+        symbol: 'NORS',
+        uri: '',
+        totalSupply: new anchor.BN(getLamports(1_000)),
+        curveSupply: new anchor.BN(getLamports(curveSupply)),
+      };
+      try {
+        await launchToken(creator, token);
+        assert.fail('Transaction failed');
+      } catch (err) {
+        assert.ok(err?.error?.errorCode?.code === 'InvalidSupply');
+      }
+    }
+  });
+
+  it('Can launch two pools under the same ticker', async () => {
+    const token = { name: 'twin', symbol: 'TWIN', uri: '' };
+    const creators = [userA, userB];
+    try {
+      const pools = [];
+      for (const creator of creators) {
+        const poolPDA = await assignNextPoolPDA(token.symbol);
+        await program.methods
+          .initialize(token)
+          .accounts({
+            signer: creator.publicKey,
+            metadata: getMetadataPDA(getMintPDA(token.symbol)),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([creator])
+          .rpc();
+        pools.push(await program.account.pool.fetch(poolPDA));
+      }
+      assert.ok(pools[0].ticker === pools[1].ticker);
+      assert.ok(!pools[0].mint.equals(pools[1].mint));
+      assert.ok(pools[1].index.eq(pools[0].index.addn(1)));
+      const tickerRecord = await program.account.tickerRecord.fetch(
+        getTickerRecordPDA(token.symbol)
+      );
+      assert.ok(tickerRecord.pool.equals(getPoolPDA(token.symbol)));
+    } catch (err) {
+      console.log('Launch shared ticker err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Can launch a pool whose mint address was funded first', async () => {
+    const token = { name: 'griefed', symbol: 'GRIF', uri: '' };
+    const creator = userA;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    // less than the mint's rent, so the launch has to top it up
+    await fundSol(mint, 0.001);
+    try {
+      await launchToken(creator, token);
+      const mintInfo = await provider.connection.getAccountInfo(mint);
+      assert.ok(mintInfo.owner.equals(TOKEN_PROGRAM_ID));
+      const rent =
+        await provider.connection.getMinimumBalanceForRentExemption(
+          mintInfo.data.length
+        );
+      assert.ok(mintInfo.lamports >= rent);
+    } catch (err) {
+      console.log('Launch pre-funded mint err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT launch a ticker reserved for another creator', async () => {
+    const token = { name: 'reserved', symbol: 'RSVD', uri: '' };
+    const creator = userA;
+    await program.methods
+      .reserveTicker(token.symbol, userB.publicKey)
+      .accounts({ admin: provider.publicKey })
+      .rpc();
+    await assignNextPoolPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(getMintPDA(token.symbol)),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'TickerReserved');
+    }
+  });
+
+  it('can NOT reuse a ticker when tickers are unique', async () => {
+    const token = { name: 'twin', symbol: 'TWIN', uri: '' };
+    const creator = userA;
+    const setUniqueTickers = (uniqueTickers: boolean) =>
+      program.methods
+        .updateConfig({ ...emptyConfigArgs, uniqueTickers })
+        .accounts({ admin: provider.publicKey })
+        .rpc();
+    await setUniqueTickers(true);
+    await assignNextPoolPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(getMintPDA(token.symbol)),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'TickerTaken');
+    } finally {
+      await setUniqueTickers(false);
+    }
+  });
+
+  it('can NOT close pool before maturity', async () => {
+    const creator = userA;
+    const pool = createdPools[0];
+    try {
+      await program.methods
+        .close()
+        .accounts({
+          signer: creator.publicKey,
+          creator: creator.publicKey,
+          pool: getPoolPDA(pool.ticker),
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolNotMatured');
+    }
+  });
+
+  it('Can buy tokens', async () => {
+    const buyer = userB;
+    const amount = 1;
+    const pool = createdPools[0];
+    try {
+      const buyerTokenAccount = await anchor.utils.token.associatedAddress(
+        {
+          mint: getMintPDA(pool.ticker),
+          owner: buyer.publicKey,
+        }
+      );
+      const solBalBefore = await getSOLBalance(buyer.publicKey);
+      const tokBalBefore = await getSPLBalance(buyerTokenAccount);
+      await buyTokens(buyer, pool.ticker, amount);
+      const solBalAfter = await getSOLBalance(buyer.publicKey);
+      const tokBalAfter = await getSPLBalance(buyerTokenAccount);
+      assert.ok(solBalAfter < solBalBefore);
+      assert.ok(tokBalAfter > tokBalBefore);
+    } catch (err) {
+      console.log('Can buy err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Emits a Trade event on buy', async () => {
+    const buyer = userB;
+    const amount = getLamports(10);
+    const pool = createdPools[0];
+    let listener: number;
+    try {
+      const event = new Promise<any>((resolve) => {
+        listener = program.addEventListener('trade', resolve);
+      });
+      await buyTokens(buyer, pool.ticker, amount);
+      const trade = await event;
+      assert.ok(trade.side.buy);
+      assert.ok(trade.trader.equals(buyer.publicKey));
+      assert.ok(trade.tokenAmount.toNumber() === amount);
+      assert.ok(trade.solAmount.toNumber() > 0);
+    } catch (err) {
+      console.log('Trade event err', err);
+      assert.fail('Transaction failed');
+    } finally {
+      await program.removeEventListener(listener);
+    }
+  });
+
+  it('Can buy tokens with an exact SOL amount', async () => {
+    const buyer = userB;
+    const solIn = getLamports(0.01);
+    const pool = createdPools[0];
+    try {
+      const buyerTokenAccount = await anchor.utils.token.associatedAddress(
+        {
+          mint: getMintPDA(pool.ticker),
+          owner: buyer.publicKey,
+        }
+      );
+      const solBalBefore = await getSOLBalance(buyer.publicKey);
+      const tokBalBefore = await getSPLBalance(buyerTokenAccount);
+      await program.methods
+        .buyExactSolIn(new anchor.BN(solIn), new anchor.BN(1))
+        .accounts({
+          buyer: buyer.publicKey,
+          pool: getPoolPDA(pool.ticker),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc();
+      const solBalAfter = await getSOLBalance(buyer.publicKey);
+      const tokBalAfter = await getSPLBalance(buyerTokenAccount);
+      assert.ok(solBalAfter < solBalBefore);
+      assert.ok(solBalBefore - solBalAfter <= solIn);
+      assert.ok(tokBalAfter > tokBalBefore);
+    } catch (err) {
+      console.log('Can buy exact SOL in err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT buy exact SOL in below min tokens out', async () => {
+    const buyer = userB;
+    const pool = createdPools[0];
+    try {
+      // one lamport buys at most a single whole token
+      await program.methods
+        .buyExactSolIn(new anchor.BN(1), new anchor.BN(getLamports(10)))
+        .accounts({
+          buyer: buyer.publicKey,
+          pool: getPoolPDA(pool.ticker),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'SlippageExceeded');
+    }
+  });
+  it('Token price increases with demand', async () => {
+    const buyer = userC;
+    const pool = createdPools[0];
+    const poolPDA = getPoolPDA(pool.ticker);
+    try {
+      const buyerTokenAccount = await anchor.utils.token.associatedAddress(
+        {
+          mint: getMintPDA(pool.ticker),
+          owner: buyer.publicKey,
+        }
+      );
+      const totalAmountToBy = getLamports(250);
+      const batchAmount = totalAmountToBy / 12;
+      let amountPurchased = 0;
+      while (amountPurchased < totalAmountToBy) {
+        const pool = await program.account.pool.fetch(poolPDA);
+        let tokPriceBefore = pool.tokPrice;
+        let solBalBefore = await getSOLBalance(buyer.publicKey);
+        let tokBalBefore = await getSPLBalance(buyerTokenAccount);
+        await buyTokens(buyer, pool.ticker, batchAmount);
+        const poolAfter = await program.account.pool.fetch(poolPDA);
+        const solBalanceAfter = await getSOLBalance(buyer.publicKey);
+        const tokenBalanceAfter = await getSPLBalance(buyerTokenAccount);
+        console.log('solBalBefore', solBalBefore);
+        console.log('solBalanceAfter', solBalanceAfter);
+        console.log('tokBalBefore', tokBalBefore);
+        console.log('tokenBalanceAfter', tokenBalanceAfter);
+        console.log('New Token price:', poolAfter.tokPrice.toNumber());
+        assert.ok(solBalanceAfter < solBalBefore);
+        assert.ok(tokenBalanceAfter > tokBalBefore);
+        assert.ok(
+          poolAfter.tokPrice.toNumber() >= tokPriceBefore.toNumber()
+        );
+        tokPriceBefore = poolAfter.tokPrice;
+        solBalBefore = solBalanceAfter;
+        amountPurchased += batchAmount;
+      }
+    } catch (err) {
+      console.log('Can buy demand error', err);
+      assert.fail('Transaction failed');
+    }
+  });
+  it('Can sell tokens', async () => {
+    const seller = userB;
+    const pool = createdPools[0];
+    const poolPDA = getPoolPDA(pool.ticker);
+    try {
+      const poolBefore = await program.account.pool.fetch(poolPDA);
+      const sellerTokenAccount =
+        await anchor.utils.token.associatedAddress({
+          mint: getMintPDA(pool.ticker),
+          owner: seller.publicKey,
+        });
+      const sellerSolBalBefore = await getSOLBalance(seller.publicKey);
+      const sellerTokBalBefore = await getSPLBalance(sellerTokenAccount);
+      const priceBefore = poolBefore.tokPrice;
+      await sellTokens(seller, pool.ticker, sellerTokBalBefore);
+      const sellerSolBalAfter = await getSOLBalance(seller.publicKey);
+      const sellerTokBalAfter = await getSPLBalance(sellerTokenAccount);
+      const poolAfter = await program.account.pool.fetch(poolPDA);
+      assert.ok(sellerSolBalAfter >= sellerSolBalBefore);
+      assert.ok(sellerTokBalAfter < sellerTokBalBefore);
+      assert.ok(poolAfter.tokPrice.toNumber() <= priceBefore.toNumber());
+    } catch (err) {
+      console.log('Can sell err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Positions record every trade a wallet makes', async () => {
+    const token = { name: 'position', symbol: 'POSN', uri: '' };
+    const trader = userC;
+    const amount = getLamports(1_000);
+    try {
+      await launchToken(userA, token);
+      await buyTokens(trader, token.symbol, amount);
+      await sellTokens(trader, token.symbol, amount / 2);
+      const position = await program.account.userPosition.fetch(
+        getPositionPDA(token.symbol, trader.publicKey)
+      );
+      assert.ok(position.user.equals(trader.publicKey));
+      assert.ok(position.pool.equals(getPoolPDA(token.symbol)));
+      assert.ok(position.tokensBought.toNumber() === amount);
+      assert.ok(position.tokensSold.toNumber() === amount / 2);
+      assert.ok(position.solSpent.gt(position.solReceived));
+      assert.ok(position.lastTradeSlot.gte(position.firstTradeSlot));
+    } catch (err) {
+      console.log('Position err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+  it('Token price decresses when supply decreases', async () => {
+    const seller = userC;
+    const pool = createdPools[0];
+    const poolPDA = getPoolPDA(pool.ticker);
+    try {
+      const sellerTokenAccount =
+        await anchor.utils.token.associatedAddress({
           mint: getMintPDA(pool.ticker),
           owner: seller.publicKey,
         });
@@ -485,57 +1470,718 @@ describe('memetik', () => {
         assert.ok(
           poolAfter.tokPrice.toNumber() <= tokPriceBefore.toNumber()
         );
-        solBalBefore = solBalanceAfter;
-        tokPriceBefore = poolAfter.tokPrice;
-        amountSold += batchAmount;
-        tokBalBefore = tokenBalanceAfter;
-        console.log('tok bal after', tokenBalanceAfter);
-        console.log('sol bal after', solBalanceAfter);
+        solBalBefore = solBalanceAfter;
+        tokPriceBefore = poolAfter.tokPrice;
+        amountSold += batchAmount;
+        tokBalBefore = tokenBalanceAfter;
+        console.log('tok bal after', tokenBalanceAfter);
+        console.log('sol bal after', solBalanceAfter);
+      }
+    } catch (err) {
+      console.log('Selling demand decreases', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT graduate pool before it matures', async () => {
+    const pool = createdPools[1];
+    try {
+      await graduatePool(pool.ticker);
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolNotMatured');
+    }
+  });
+
+  it('can NOT graduate through another cp-swap config', async () => {
+    const pool = createdPools[1];
+    const graduationAccounts = await getGraduationAccounts(pool.ticker);
+    try {
+      await program.methods
+        .graduate()
+        .accountsPartial({
+          ...graduationAccounts,
+          ammConfig: anchor.web3.Keypair.generate().publicKey,
+        })
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'InvalidAmmConfig');
+    }
+  });
+
+  it('can NOT buy when the price moves past max SOL cost', async () => {
+    const buyer = userA;
+    const frontRunner = userC;
+    const amount = getLamports(1_000);
+    const pool = createdPools[1];
+    const solVaultPDA = getSolVaultPDA(pool.ticker);
+    const vaultBalBefore = await getSOLBalance(solVaultPDA);
+    await buyTokens(buyer, pool.ticker, amount);
+    // what the same buy cost before anyone else traded
+    const quotedCost = (await getSOLBalance(solVaultPDA)) - vaultBalBefore;
+    await buyTokens(frontRunner, pool.ticker, amount);
+    try {
+      await buyTokens(
+        buyer,
+        pool.ticker,
+        amount,
+        new anchor.BN(quotedCost)
+      );
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'SlippageExceeded');
+    }
+  });
+
+  it('can NOT sell when the price moves below min SOL out', async () => {
+    const seller = userA;
+    const frontRunner = userC;
+    const amount = getLamports(1_000);
+    const pool = createdPools[1];
+    const solVaultPDA = getSolVaultPDA(pool.ticker);
+    // the front runner sells the same amount first, taking the proceeds
+    // the seller was quoted
+    const vaultBalBefore = await getSOLBalance(solVaultPDA);
+    await sellTokens(frontRunner, pool.ticker, amount);
+    const quotedProceeds =
+      vaultBalBefore - (await getSOLBalance(solVaultPDA));
+    try {
+      await sellTokens(
+        seller,
+        pool.ticker,
+        amount,
+        new anchor.BN(quotedProceeds)
+      );
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'SlippageExceeded');
+    }
+  });
+
+  // logs what a trade costs, the budget is the default per-instruction limit
+  it('Buys and sells fit the default compute budget', async () => {
+    const trader = userA;
+    const amount = getLamports(100);
+    const pool = createdPools[1];
+    try {
+      const buyUnits = await getComputeUnits(
+        await buyTokens(trader, pool.ticker, amount)
+      );
+      const sellUnits = await getComputeUnits(
+        await sellTokens(trader, pool.ticker, amount)
+      );
+      console.log('buy compute units:', buyUnits);
+      console.log('sell compute units:', sellUnits);
+      assert.ok(buyUnits > 0 && buyUnits < 200_000);
+      assert.ok(sellUnits > 0 && sellUnits < 200_000);
+    } catch (err) {
+      console.log('Benchmark err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Holders can sell the entire supply back', async () => {
+    const ticker = 'LINE';
+    const poolPDA = getPoolPDA(ticker);
+    const mint = getMintPDA(ticker);
+    const holders = [userA, userB];
+    try {
+      for (const holder of holders) {
+        await buyTokens(holder, ticker, getLamports(1_000));
+      }
+      for (const holder of holders) {
+        const holderTokenAccount =
+          await anchor.utils.token.associatedAddress({
+            mint,
+            owner: holder.publicKey,
+          });
+        const balance = await provider.connection.getTokenAccountBalance(
+          holderTokenAccount
+        );
+        await sellTokens(holder, ticker, Number(balance.value.amount));
       }
+      const poolAfter = await program.account.pool.fetch(poolPDA);
+      const mintInfo = await provider.connection.getTokenSupply(mint);
+      const vaultBalance = await getSOLBalance(getSolVaultPDA(ticker));
+      const vaultRent =
+        await provider.connection.getMinimumBalanceForRentExemption(0);
+      assert.ok(mintInfo.value.amount === '0');
+      // buys round up and sells round down, so only dust stays behind
+      assert.ok(poolAfter.realSolReserves.toNumber() < 1_000);
+      assert.ok(
+        vaultBalance >= vaultRent + poolAfter.realSolReserves.toNumber()
+      );
     } catch (err) {
-      console.log('Selling demand decreases', err);
+      console.log('Sell entire supply err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('SOL sent straight to a sol vault does NOT mature its pool', async () => {
+    const ticker = 'LINE';
+    const poolPDA = getPoolPDA(ticker);
+    const config = await program.account.globalConfig.fetch(
+      getConfigPDA()
+    );
+    // more than the maturity balance, without buying a single token
+    await fundSol(
+      getSolVaultPDA(ticker),
+      getSol(config.maturityBalance.toNumber()) * 2
+    );
+    await buyTokens(userA, ticker, getLamports(1));
+    const pool = await program.account.pool.fetch(poolPDA);
+    assert.ok(pool.status.active);
+    assert.ok(pool.realSolReserves.lt(config.maturityBalance));
+    try {
+      await graduatePool(ticker);
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolNotMatured');
+    }
+  });
+
+  it('Pool graduates to cp-swap once it matures', async () => {
+    const token = tokens[2];
+    const creator = userA;
+    const buyer = userB;
+    await assignNextPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: getMetadataPDA(mint),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      // ~1.04 SOL along the curve, enough to cross the maturity balance
+      await buyTokens(buyer, token.symbol, getLamports(31_000));
+      await graduatePool(token.symbol);
+      const pool = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      const { poolState, creatorLpToken } = await getGraduationAccounts(
+        token.symbol
+      );
+      const poolStateInfo = await provider.connection.getAccountInfo(
+        poolState
+      );
+      const tokenVaultBal = await getSPLBalance(
+        getCpSwapVaultAddress(poolState, mint)
+      );
+      const wsolVaultBal = await getSPLBalance(
+        getCpSwapVaultAddress(poolState, SOL_MINT)
+      );
+      const lpBal = await getSPLBalance(creatorLpToken);
+      assert.ok(pool.status.graduated);
+      assert.ok(poolStateInfo.owner.equals(raydiumSwapProgramId));
+      assert.ok(tokenVaultBal > 0);
+      assert.ok(wsolVaultBal > 0);
+      // the default LP policy burns the liquidity on graduation
+      assert.ok('burn' in pool.lpPolicy);
+      assert.ok(lpBal === 0);
+    } catch (err) {
+      console.log('Graduate pool err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Pool graduates once its curve sells out', async () => {
+    // ~0.69 SOL along the curve, under the maturity balance, and more
+    // than the 3k token LP reserve can match at the curve's last price
+    const curveSupply = getLamports(27_000);
+    const token = {
+      name: 'sold out',
+      symbol: 'SOLD',
+      uri: '',
+      totalSupply: new anchor.BN(getLamports(30_000)),
+      curveSupply: new anchor.BN(curveSupply),
+    };
+    const buyer = userB;
+    try {
+      await launchToken(userA, token);
+      await buyTokens(buyer, token.symbol, curveSupply);
+      const config = await program.account.globalConfig.fetch(
+        getConfigPDA()
+      );
+      const poolBefore = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      assert.ok(poolBefore.realSolReserves.lt(config.maturityBalance));
+      await graduatePool(token.symbol);
+      const pool = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      const { poolState } = await getGraduationAccounts(token.symbol);
+      const tokenVaultBal = await getSPLBalance(
+        getCpSwapVaultAddress(poolState, getMintPDA(token.symbol))
+      );
+      const wsolVaultBal = await getSPLBalance(
+        getCpSwapVaultAddress(poolState, SOL_MINT)
+      );
+      const ammPrice = (wsolVaultBal * LAMPORTS_PER_SOL) / tokenVaultBal;
+      assert.ok(pool.status.graduated);
+      // the AMM opens at the curve's last price, the SOL the reserve can't
+      // match stays in the pool
+      assert.ok(Math.abs(ammPrice / pool.tokPrice.toNumber() - 1) < 0.01);
+      assert.ok(pool.realSolReserves.gtn(0));
+    } catch (err) {
+      console.log('Graduate sold out pool err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT sell into a graduated pool', async () => {
+    const seller = userB;
+    const token = tokens[2];
+    try {
+      await sellTokens(seller, token.symbol, getLamports(1));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolAlreadyGraduated');
+    }
+  });
+
+  it('can NOT claim LP tokens that were burned', async () => {
+    const creator = userA;
+    const token = tokens[2];
+    const { lpMint } = await program.account.pool.fetch(
+      getPoolPDA(token.symbol)
+    );
+    try {
+      await program.methods
+        .claimLpTokens()
+        .accountsPartial({
+          creator: creator.publicKey,
+          pool: getPoolPDA(token.symbol),
+          lpMint,
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'LpTokensNotVesting');
+    }
+  });
+
+  it('creator can claim LP tokens vesting to them', async () => {
+    const token = { name: 'vesting', symbol: 'VEST', uri: '' };
+    const creator = userA;
+    const setLpPolicy = (lpPolicy: any) =>
+      program.methods
+        .updateConfig({ ...emptyConfigArgs, lpPolicy })
+        .accounts({ admin: provider.publicKey })
+        .rpc();
+    await setLpPolicy({
+      vest: { cliff: new anchor.BN(0), duration: new anchor.BN(1) },
+    });
+    try {
+      await launchToken(creator, token);
+      await buyTokens(userB, token.symbol, getLamports(31_000));
+      await graduatePool(token.symbol);
+      await sleep(2000);
+      const { lpMint } = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      await program.methods
+        .claimLpTokens()
+        .accountsPartial({
+          creator: creator.publicKey,
+          pool: getPoolPDA(token.symbol),
+          lpMint,
+        })
+        .signers([creator])
+        .rpc();
+      const pool = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      const creatorLpBal = await getSPLBalance(
+        getAssociatedTokenAddressSync(lpMint, creator.publicKey)
+      );
+      assert.ok(pool.lpVestingAmount.gtn(0));
+      assert.ok(pool.lpClaimed.eq(pool.lpVestingAmount));
+      assert.ok(creatorLpBal > 0);
+    } catch (err) {
+      console.log('Claim LP tokens err', err);
+      assert.fail('Transaction failed');
+    } finally {
+      await setLpPolicy({ burn: {} });
+    }
+  });
+
+  it('creator can claim trading fees', async () => {
+    const creator = userA;
+    const pool = createdPools[0];
+    try {
+      const solBalBefore = await getSOLBalance(creator.publicKey);
+      await program.methods
+        .claimCreatorFees()
+        .accounts({
+          creator: creator.publicKey,
+          pool: getPoolPDA(pool.ticker),
+        })
+        .signers([creator])
+        .rpc();
+      const solBalAfter = await getSOLBalance(creator.publicKey);
+      assert.ok(solBalAfter > solBalBefore);
+    } catch (err) {
+      console.log('Claim creator fees err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('bad actor can NOT claim creator fees', async () => {
+    const badActor = userB;
+    const pool = createdPools[0];
+    try {
+      await program.methods
+        .claimCreatorFees()
+        .accounts({
+          creator: badActor.publicKey,
+          pool: getPoolPDA(pool.ticker),
+        })
+        .signers([badActor])
+        .rpc();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'NotPoolCreator');
+    }
+  });
+
+  it('Can sweep protocol fees to the fee receiver', async () => {
+    const protocolFeeReceiver = anchor.web3.Keypair.generate().publicKey;
+    try {
+      await program.methods
+        .updateConfig({ ...emptyConfigArgs, protocolFeeReceiver })
+        .accounts({ admin: provider.publicKey })
+        .rpc();
+      // the receiver must already be rent exempt to take small sweeps
+      await fundSol(protocolFeeReceiver, 1);
+      const solBalBefore = await getSOLBalance(protocolFeeReceiver);
+      await program.methods
+        .claimProtocolFees()
+        .accountsPartial({ protocolFeeReceiver })
+        .rpc();
+      const solBalAfter = await getSOLBalance(protocolFeeReceiver);
+      assert.ok(solBalAfter > solBalBefore);
+    } catch (err) {
+      console.log('Claim protocol fees err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT sweep protocol fees to another account', async () => {
+    try {
+      await program.methods
+        .claimProtocolFees()
+        .accountsPartial({ protocolFeeReceiver: userB.publicKey })
+        .rpc();
       assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'ConstraintAddress');
     }
   });
 
-  it('bad actor can NOT close pool', async () => {
+  it('bad actor can NOT take the escrow of a pool they close', async () => {
     const badActor = userB;
     const pool = createdPools[0];
     const maturityTimeStampMs = pool.maturityTime.toNumber() * 1000;
     try {
       await waitUntilTime(maturityTimeStampMs);
       await program.methods
-        .close(pool.ticker)
+        .close()
         .accounts({
+          signer: badActor.publicKey,
           creator: badActor.publicKey,
+          pool: getPoolPDA(pool.ticker),
         })
         .signers([badActor])
         .rpc();
       assert.fail('Transaction failed');
     } catch (err) {
-      assert.ok(err?.error?.errorCode?.code === 'NotPoolCreator');
+      assert.ok(err?.error?.errorCode?.code === 'NotEscrowOwner');
     }
   });
 
-  it('creator can close pool after maturity date', async () => {
+  it('anyone can close a pool after its maturity date', async () => {
     const creator = userA;
+    const cranker = userC;
     const pool = createdPools[0];
     const maturityTimeStampMs = pool.maturityTime.toNumber() * 1000;
     const creatorBalBefore = await getSOLBalance(creator.publicKey);
     try {
       await waitUntilTime(maturityTimeStampMs);
       await program.methods
-        .close(pool.ticker)
+        .close()
         .accounts({
+          signer: cranker.publicKey,
           creator: creator.publicKey,
+          pool: getPoolPDA(pool.ticker),
         })
-        .signers([creator])
+        .signers([cranker])
         .rpc();
       const creatorBalAfter = await getSOLBalance(creator.publicKey);
+      const poolAfter = await program.account.pool.fetch(
+        getPoolPDA(pool.ticker)
+      );
       assert.ok(creatorBalAfter > creatorBalBefore);
+      assert.ok(poolAfter.status.failed);
     } catch (err) {
       console.log('Close pool error', err);
       assert.fail('Transaction failed');
     }
   });
+
+  it('anyone can fail a sold out pool too small to graduate', async () => {
+    const creator = userA;
+    const cranker = userC;
+    const poolPDA = getPoolPDA('FULL');
+    const pool = await program.account.pool.fetch(poolPDA);
+    try {
+      await waitUntilTime(pool.maturityTime.toNumber() * 1000);
+      await program.methods
+        .close()
+        .accounts({
+          signer: cranker.publicKey,
+          creator: creator.publicKey,
+          pool: poolPDA,
+        })
+        .signers([cranker])
+        .rpc();
+      const poolAfter = await program.account.pool.fetch(poolPDA);
+      assert.ok(poolAfter.status.failed);
+      assert.ok(poolAfter.failedBalance.eq(pool.realSolReserves));
+    } catch (err) {
+      console.log('Close sold out pool error', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('anyone can fail a matured pool that never graduates', async () => {
+    const token = {
+      name: 'late',
+      symbol: 'LATE',
+      uri: '',
+      maturityDuration: new anchor.BN(10),
+    };
+    const creator = userA;
+    const cranker = userC;
+    const closePool = () =>
+      program.methods
+        .close()
+        .accounts({
+          signer: cranker.publicKey,
+          creator: creator.publicKey,
+          pool: getPoolPDA(token.symbol),
+        })
+        .signers([cranker])
+        .rpc();
+    await launchToken(creator, token);
+    await buyTokens(userB, token.symbol, getLamports(31_000));
+    const pool = await program.account.pool.fetch(
+      getPoolPDA(token.symbol)
+    );
+    assert.ok('matured' in pool.status);
+    // past its maturity time, still within the graduation grace period
+    await waitUntilTime(pool.maturityTime.toNumber() * 1000);
+    try {
+      await closePool();
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolAlreadyMatured');
+    }
+    try {
+      await waitUntilTime(
+        (pool.maturityTime.toNumber() + GRADUATION_GRACE_SECONDS) * 1000
+      );
+      await closePool();
+      const poolAfter = await program.account.pool.fetch(
+        getPoolPDA(token.symbol)
+      );
+      assert.ok(poolAfter.status.failed);
+      assert.ok(poolAfter.failedBalance.eq(pool.realSolReserves));
+    } catch (err) {
+      console.log('Close ungraduated pool error', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('can NOT buy from a failed pool', async () => {
+    const buyer = userB;
+    const pool = createdPools[0];
+    try {
+      await buyTokens(buyer, pool.ticker, getLamports(1));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolFailed');
+    }
+  });
+
+  it('can NOT sell into a failed pool', async () => {
+    const seller = userC;
+    const pool = createdPools[0];
+    try {
+      await sellTokens(seller, pool.ticker, getLamports(1));
+      assert.fail('Transaction failed');
+    } catch (err) {
+      assert.ok(err?.error?.errorCode?.code === 'PoolFailed');
+    }
+  });
+
+  it('Holder can claim a refund from a failed pool', async () => {
+    const holder = userC;
+    const pool = createdPools[0];
+    const claimRefund = () =>
+      program.methods
+        .claimRefund()
+        .accounts({
+          holder: holder.publicKey,
+          pool: getPoolPDA(pool.ticker),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+    try {
+      const holderTokenAccount = await anchor.utils.token.associatedAddress(
+        {
+          mint: getMintPDA(pool.ticker),
+          owner: holder.publicKey,
+        }
+      );
+      const poolAfterFailure = await program.account.pool.fetch(
+        getPoolPDA(pool.ticker)
+      );
+      const tokBalBefore = new anchor.BN(
+        (
+          await provider.connection.getTokenAccountBalance(
+            holderTokenAccount
+          )
+        ).value.amount
+      );
+      const solBalBefore = await getSOLBalance(holder.publicKey);
+      await claimRefund();
+      const tokBalAfter = await getSPLBalance(holderTokenAccount);
+      const solBalAfter = await getSOLBalance(holder.publicKey);
+      // pro-rata share of the balance snapshotted at failure
+      const expectedRefund = poolAfterFailure.failedBalance
+        .mul(tokBalBefore)
+        .div(poolAfterFailure.failedSupply)
+        .toNumber();
+      assert.ok(tokBalBefore.gtn(0));
+      assert.ok(tokBalAfter === 0);
+      assert.ok(solBalAfter - solBalBefore === expectedRefund);
+      // claiming again pays nothing
+      await claimRefund();
+      assert.ok((await getSOLBalance(holder.publicKey)) === solBalAfter);
+    } catch (err) {
+      console.log('Claim refund err', err);
+      assert.fail('Transaction failed');
+    }
+  });
+
+  it('Failed pool closes with transfer fees withheld in its mint', async () => {
+    const token = {
+      name: 'withheld',
+      symbol: 'WHLD',
+      uri: '',
+      maturityDuration: new anchor.BN(30),
+      transferFee: {
+        basisPoints: 100,
+        maximumFee: new anchor.BN(getLamports(1_000)),
+      },
+    };
+    const creator = userA;
+    const holders = [userB, userC];
+    await assignNextPoolPDA(token.symbol);
+    const poolPDA = getPoolPDA(token.symbol);
+    const mint = getMintPDA(token.symbol);
+    const [source, destination] = holders.map((holder) =>
+      getAssociatedTokenAddressSync(
+        mint,
+        holder.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    try {
+      await program.methods
+        .initialize(token)
+        .accounts({
+          signer: creator.publicKey,
+          metadata: null,
+          tokenMetadataProgram: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([creator])
+        .rpc();
+      await buyTokens(userB, token.symbol, getLamports(1_000));
+      // a transfer between holders withholds the fee, harvesting moves it
+      // into the mint where no holder can redeem it
+      await createAssociatedTokenAccount(
+        provider.connection,
+        userC,
+        mint,
+        userC.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await transferChecked(
+        provider.connection,
+        userB,
+        source,
+        mint,
+        destination,
+        userB,
+        getLamports(500),
+        9,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await harvestWithheldTokensToMint(
+        provider.connection,
+        userB,
+        mint,
+        [destination],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      const pool = await program.account.pool.fetch(poolPDA);
+      await waitUntilTime(pool.maturityTime.toNumber() * 1000);
+      await program.methods
+        .close()
+        .accounts({
+          signer: userC.publicKey,
+          creator: creator.publicKey,
+          pool: poolPDA,
+        })
+        .signers([userC])
+        .rpc();
+      for (const holder of holders) {
+        await program.methods
+          .claimRefund()
+          .accounts({
+            holder: holder.publicKey,
+            pool: poolPDA,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([holder])
+          .rpc();
+      }
+      const poolAfter = await program.account.pool.fetch(poolPDA);
+      // 1% of the 500 tokens sent was withheld
+      assert.ok(
+        poolAfter.failedSupply.eq(new anchor.BN(getLamports(1_000 - 5)))
+      );
+      assert.ok(poolAfter.status.closed);
+      // only rounding dust stays behind
+      assert.ok(poolAfter.realSolReserves.toNumber() < 10);
+    } catch (err) {
+      console.log('Withheld fees refund err', err);
+      assert.fail('Transaction failed');
+    }
+  });
 });